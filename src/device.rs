@@ -0,0 +1,176 @@
+//! Vulkan calls the library makes, so the shader modules and layouts can be created without a real device.
+
+use ash::{
+    vk::{
+        self, AllocationCallbacks, DescriptorSetLayout, DescriptorSetLayoutCreateInfo,
+        PipelineLayout, PipelineLayoutCreateInfo, ShaderModule, ShaderModuleCreateInfo,
+    },
+    Device,
+};
+
+/// The device the shader modules and the pipeline layouts of the programs are created on and destroyed with.
+///
+/// Implemented for `ash::Device`, for the `MockDevice`, which can be used to test
+/// the shader stages creation without a driver, and for the `NoDevice` of `ShaderStage::without_device`.
+pub trait ShaderDevice {
+    /// Creates the shader module, like `vkCreateShaderModule`.
+    /// # Safety
+    ///
+    /// `create_info` must be a valid `VkShaderModuleCreateInfo`.
+    unsafe fn create_shader_module(
+        &self,
+        create_info: &ShaderModuleCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<ShaderModule, vk::Result>;
+
+    /// Destroys the shader module, like `vkDestroyShaderModule`.
+    /// # Safety
+    ///
+    /// `module` must be created by this device with compatible `allocation_callbacks`.
+    unsafe fn destroy_shader_module(
+        &self,
+        module: ShaderModule,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    );
+
+    /// Creates the descriptor set layout, like `vkCreateDescriptorSetLayout`.
+    /// # Safety
+    ///
+    /// `create_info` must be a valid `VkDescriptorSetLayoutCreateInfo`.
+    unsafe fn create_descriptor_set_layout(
+        &self,
+        create_info: &DescriptorSetLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<DescriptorSetLayout, vk::Result>;
+
+    /// Destroys the descriptor set layout, like `vkDestroyDescriptorSetLayout`.
+    /// # Safety
+    ///
+    /// `layout` must be created by this device with compatible `allocation_callbacks`.
+    unsafe fn destroy_descriptor_set_layout(
+        &self,
+        layout: DescriptorSetLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    );
+
+    /// Creates the pipeline layout, like `vkCreatePipelineLayout`.
+    /// # Safety
+    ///
+    /// `create_info` must be a valid `VkPipelineLayoutCreateInfo`.
+    unsafe fn create_pipeline_layout(
+        &self,
+        create_info: &PipelineLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<PipelineLayout, vk::Result>;
+
+    /// Destroys the pipeline layout, like `vkDestroyPipelineLayout`.
+    /// # Safety
+    ///
+    /// `layout` must be created by this device with compatible `allocation_callbacks`.
+    unsafe fn destroy_pipeline_layout(
+        &self,
+        layout: PipelineLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    );
+}
+
+impl ShaderDevice for Device {
+    unsafe fn create_shader_module(
+        &self,
+        create_info: &ShaderModuleCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<ShaderModule, vk::Result> {
+        Device::create_shader_module(self, create_info, allocation_callbacks)
+    }
+
+    unsafe fn destroy_shader_module(
+        &self,
+        module: ShaderModule,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        Device::destroy_shader_module(self, module, allocation_callbacks)
+    }
+    unsafe fn create_descriptor_set_layout(
+        &self,
+        create_info: &DescriptorSetLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<DescriptorSetLayout, vk::Result> {
+        Device::create_descriptor_set_layout(self, create_info, allocation_callbacks)
+    }
+
+    unsafe fn destroy_descriptor_set_layout(
+        &self,
+        layout: DescriptorSetLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        Device::destroy_descriptor_set_layout(self, layout, allocation_callbacks)
+    }
+
+    unsafe fn create_pipeline_layout(
+        &self,
+        create_info: &PipelineLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<PipelineLayout, vk::Result> {
+        Device::create_pipeline_layout(self, create_info, allocation_callbacks)
+    }
+
+    unsafe fn destroy_pipeline_layout(
+        &self,
+        layout: PipelineLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        Device::destroy_pipeline_layout(self, layout, allocation_callbacks)
+    }
+}
+
+/// The device of `ShaderStage::without_device`, which only plans the shader stages.
+/// Every creation fails with `ERROR_INITIALIZATION_FAILED`, so `build` fails on the first shader module.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoDevice;
+
+impl ShaderDevice for NoDevice {
+    unsafe fn create_shader_module(
+        &self,
+        _create_info: &ShaderModuleCreateInfo,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<ShaderModule, vk::Result> {
+        Err(vk::Result::ERROR_INITIALIZATION_FAILED)
+    }
+
+    unsafe fn destroy_shader_module(
+        &self,
+        _module: ShaderModule,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+    }
+
+    unsafe fn create_descriptor_set_layout(
+        &self,
+        _create_info: &DescriptorSetLayoutCreateInfo,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<DescriptorSetLayout, vk::Result> {
+        Err(vk::Result::ERROR_INITIALIZATION_FAILED)
+    }
+
+    unsafe fn destroy_descriptor_set_layout(
+        &self,
+        _layout: DescriptorSetLayout,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+    }
+
+    unsafe fn create_pipeline_layout(
+        &self,
+        _create_info: &PipelineLayoutCreateInfo,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<PipelineLayout, vk::Result> {
+        Err(vk::Result::ERROR_INITIALIZATION_FAILED)
+    }
+
+    unsafe fn destroy_pipeline_layout(
+        &self,
+        _layout: PipelineLayout,
+        _allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    error::Error,
+    ffi::NulError,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use ash::vk;
+
+use crate::{SpecializationConstantType, SpecializationValue};
+
+/// Errors that can happen while creating shader stages.
+#[derive(Debug)]
+pub enum ShaderCreatorError {
+    /// The directory with compiled shaders can't be read.
+    ReadDirectory { path: PathBuf, source: io::Error },
+    /// The name of a compiled shader or a subdirectory to scan isn't valid UTF-8, so its stage can't be defined.
+    /// Hidden, excluded and other skipped files are filtered by their lossy names and never fail.
+    /// Like the errors of the compiled shaders, it's subject to the `ErrorPolicy`.
+    NonUtf8Path { path: PathBuf },
+    /// The compiled shader file can't be opened.
+    OpenFile { path: PathBuf, source: io::Error },
+    /// The compiled shader file can't be read.
+    ReadFile { path: PathBuf, source: io::Error },
+    /// Size of the compiled shader isn't a multiple of 4 or is smaller than the SPIR-V header.
+    InvalidSpirvSize { path: PathBuf, size: usize },
+    /// The compiled shader doesn't start with the SPIR-V magic number.
+    InvalidSpirvMagic { path: PathBuf, magic: u32 },
+    /// The instructions of the compiled shader are malformed, e.g. the module is truncated, an instruction
+    /// has the word count of 0, its types are cyclic or the size of its block overflows `u32`.
+    MalformedSpirv { path: PathBuf },
+    /// `vkCreateShaderModule` failed for the compiled shader.
+    CreateShaderModule { path: PathBuf, result: vk::Result },
+    /// The shader stage of the compiled shader can't be defined.
+    UnknownStage { path: PathBuf },
+    /// Two compiled shaders define the same stage of the same program and `DuplicatePolicy::Error` is used.
+    DuplicateShader {
+        program_name: String,
+        variant: Option<String>,
+        stage: vk::ShaderStageFlags,
+        first_path: PathBuf,
+        second_path: PathBuf,
+    },
+    /// The module has several entry points of the same stage, e.g. `PSMain` and `OtherPSMain`,
+    /// and `DuplicatePolicy::Error` is used.
+    DuplicateEntryPoint {
+        path: PathBuf,
+        stage: vk::ShaderStageFlags,
+        first_entry_point: String,
+        second_entry_point: String,
+    },
+    /// The include or exclude glob pattern is invalid.
+    InvalidGlobPattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    /// `SpecializationConstants` of the stage set the constant its module doesn't declare,
+    /// for the defaults of every stage no stage of the program declares it.
+    /// The raw `SpecializationInfo` is reported only if the module declares other constants.
+    UnknownSpecializationConstant {
+        path: PathBuf,
+        stage: vk::ShaderStageFlags,
+        constant_id: u32,
+    },
+    /// The specialization info of the stage sets the constant with the size or the value type that doesn't match
+    /// its declaration. `value` is `None` for the raw `SpecializationInfo`.
+    SpecializationConstantMismatch {
+        path: PathBuf,
+        stage: vk::ShaderStageFlags,
+        constant_id: u32,
+        expected: SpecializationConstantType,
+        size: usize,
+        value: Option<SpecializationValue>,
+    },
+    /// The stage declares the binding with the descriptor type that differs from the one
+    /// declared by another stage of the same program.
+    DescriptorTypeMismatch {
+        path: PathBuf,
+        stage: vk::ShaderStageFlags,
+        set: u32,
+        binding: u32,
+        expected: vk::DescriptorType,
+        found: vk::DescriptorType,
+    },
+    /// The member of the push constant block of the stage partially overlaps the member of the same block
+    /// of another stage of the same program, or has the same offset and size, but another type.
+    PushConstantMismatch {
+        path: PathBuf,
+        stage: vk::ShaderStageFlags,
+        offset: u32,
+        size: u32,
+        type_name: String,
+        other_stage: vk::ShaderStageFlags,
+        other_offset: u32,
+        other_size: u32,
+        other_type_name: String,
+    },
+    /// The binding of the program is a runtime array or an array of unresolved length, whose count is reflected as 0,
+    /// and `BindingConfig` doesn't set its count.
+    UnresolvedDescriptorCount {
+        program_name: String,
+        variant: Option<String>,
+        set: u32,
+        binding: u32,
+    },
+    /// Vulkan failed to create the descriptor set layout of the program.
+    CreateDescriptorSetLayout {
+        program_name: String,
+        variant: Option<String>,
+        set: u32,
+        result: vk::Result,
+    },
+    /// Vulkan failed to create the pipeline layout of the program.
+    CreatePipelineLayout {
+        program_name: String,
+        variant: Option<String>,
+        result: vk::Result,
+    },
+    /// The main function name or an entry point name override contains an interior nul byte.
+    // `std::ffi::NulError` is stable since 1.0, clippy checks the `alloc::ffi` path it was moved to in 1.64.
+    #[allow(clippy::incompatible_msrv)]
+    InvalidMainFunctionName { name: String, source: NulError },
+}
+
+impl ShaderCreatorError {
+    /// Path of the directory or the compiled shader the error is about, the second path for `DuplicateShader`.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::ReadDirectory { path, .. }
+            | Self::NonUtf8Path { path }
+            | Self::OpenFile { path, .. }
+            | Self::ReadFile { path, .. }
+            | Self::InvalidSpirvSize { path, .. }
+            | Self::InvalidSpirvMagic { path, .. }
+            | Self::MalformedSpirv { path }
+            | Self::DuplicateEntryPoint { path, .. }
+            | Self::CreateShaderModule { path, .. }
+            | Self::UnknownStage { path }
+            | Self::UnknownSpecializationConstant { path, .. }
+            | Self::SpecializationConstantMismatch { path, .. }
+            | Self::DescriptorTypeMismatch { path, .. }
+            | Self::PushConstantMismatch { path, .. } => Some(path),
+            Self::DuplicateShader { second_path, .. } => Some(second_path),
+            Self::InvalidGlobPattern { .. }
+            | Self::InvalidMainFunctionName { .. }
+            | Self::UnresolvedDescriptorCount { .. }
+            | Self::CreateDescriptorSetLayout { .. }
+            | Self::CreatePipelineLayout { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ShaderCreatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadDirectory { path, source } => {
+                write!(f, "failed to read directory path at {:?}: {}", path, source)
+            }
+            Self::NonUtf8Path { path } => write!(f, "path {:?} isn't valid UTF-8", path),
+            Self::OpenFile { path, source } => write!(
+                f,
+                "failed to open compiled shader file at {:?}: {}",
+                path, source
+            ),
+            Self::ReadFile { path, source } => write!(
+                f,
+                "failed to read compiled shader file at {:?}: {}",
+                path, source
+            ),
+            Self::InvalidSpirvSize { path, size } => write!(
+                f,
+                "compiled shader {:?} has invalid SPIR-V size of {} bytes",
+                path, size
+            ),
+            Self::InvalidSpirvMagic { path, magic } => write!(
+                f,
+                "compiled shader {:?} has invalid SPIR-V magic number {:#010x}",
+                path, magic
+            ),
+            Self::MalformedSpirv { path } => {
+                write!(f, "compiled shader {:?} has malformed SPIR-V instructions", path)
+            }
+            Self::CreateShaderModule { path, result } => write!(
+                f,
+                "failed to create shader module from {:?}: {}",
+                path, result
+            ),
+            Self::UnknownStage { path } => {
+                write!(f, "failed to define shader type of {:?}", path)
+            }
+            Self::DuplicateShader {
+                program_name,
+                variant,
+                stage,
+                first_path,
+                second_path,
+            } => {
+                write!(f, "{:?} stage of the program {:?}", stage, program_name)?;
+                if let Some(variant) = variant {
+                    write!(f, " (variant {:?})", variant)?;
+                }
+                write!(
+                    f,
+                    " is defined by both {:?} and {:?}",
+                    first_path, second_path
+                )
+            }
+            Self::DuplicateEntryPoint {
+                path,
+                stage,
+                first_entry_point,
+                second_entry_point,
+            } => write!(
+                f,
+                "compiled shader {:?} has both {:?} and {:?} entry points of the {:?} stage",
+                path, first_entry_point, second_entry_point, stage
+            ),
+            Self::UnknownSpecializationConstant {
+                path,
+                stage,
+                constant_id,
+            } => write!(
+                f,
+                "{:?} stage of {:?} doesn't declare specialization constant {}",
+                stage, path, constant_id
+            ),
+            Self::SpecializationConstantMismatch {
+                path,
+                stage,
+                constant_id,
+                expected,
+                size,
+                value,
+            } => {
+                write!(
+                    f,
+                    "specialization constant {} of {:?} stage of {:?} is {:?}, but ",
+                    constant_id, stage, path, expected
+                )?;
+                match value {
+                    Some(value) => write!(f, "{:?} is provided", value),
+                    None => write!(f, "{} bytes are provided", size),
+                }
+            }
+            Self::DescriptorTypeMismatch {
+                path,
+                stage,
+                set,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{:?} stage of {:?} declares binding {} of set {} as {:?}, but other stages declare it as {:?}",
+                stage, path, binding, set, found, expected
+            ),
+            Self::PushConstantMismatch {
+                path,
+                stage,
+                offset,
+                size,
+                type_name,
+                other_stage,
+                other_offset,
+                other_size,
+                other_type_name,
+            } => write!(
+                f,
+                "{:?} stage of {:?} declares push constant {} of {} bytes at offset {}, \
+                 but {:?} stage declares push constant {} of {} bytes at offset {}",
+                stage,
+                path,
+                type_name,
+                size,
+                offset,
+                other_stage,
+                other_type_name,
+                other_size,
+                other_offset
+            ),
+            Self::UnresolvedDescriptorCount {
+                program_name,
+                variant,
+                set,
+                binding,
+            } => {
+                write!(
+                    f,
+                    "descriptor count of binding {} of set {} of the program {:?}",
+                    binding, set, program_name
+                )?;
+                if let Some(variant) = variant {
+                    write!(f, " (variant {:?})", variant)?;
+                }
+                write!(f, " can't be reflected and isn't configured")
+            }
+            Self::CreateDescriptorSetLayout {
+                program_name,
+                variant,
+                set,
+                result,
+            } => {
+                write!(
+                    f,
+                    "failed to create layout of descriptor set {} of the program {:?}",
+                    set, program_name
+                )?;
+                if let Some(variant) = variant {
+                    write!(f, " (variant {:?})", variant)?;
+                }
+                write!(f, ": {}", result)
+            }
+            Self::CreatePipelineLayout {
+                program_name,
+                variant,
+                result,
+            } => {
+                write!(
+                    f,
+                    "failed to create pipeline layout of the program {:?}",
+                    program_name
+                )?;
+                if let Some(variant) = variant {
+                    write!(f, " (variant {:?})", variant)?;
+                }
+                write!(f, ": {}", result)
+            }
+            Self::InvalidGlobPattern { pattern, source } => {
+                write!(f, "invalid glob pattern {:?}: {}", pattern, source)
+            }
+            Self::InvalidMainFunctionName { name, source } => {
+                write!(f, "invalid main function name {:?}: {}", name, source)
+            }
+        }
+    }
+}
+
+impl Error for ShaderCreatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ReadDirectory { source, .. }
+            | Self::OpenFile { source, .. }
+            | Self::ReadFile { source, .. } => Some(source),
+            Self::CreateShaderModule { result, .. }
+            | Self::CreateDescriptorSetLayout { result, .. }
+            | Self::CreatePipelineLayout { result, .. } => Some(result),
+            Self::InvalidGlobPattern { source, .. } => Some(source),
+            Self::InvalidMainFunctionName { source, .. } => Some(source),
+            Self::NonUtf8Path { .. }
+            | Self::InvalidSpirvSize { .. }
+            | Self::InvalidSpirvMagic { .. }
+            | Self::MalformedSpirv { .. }
+            | Self::UnknownStage { .. }
+            | Self::DuplicateShader { .. }
+            | Self::DuplicateEntryPoint { .. }
+            | Self::UnknownSpecializationConstant { .. }
+            | Self::SpecializationConstantMismatch { .. }
+            | Self::DescriptorTypeMismatch { .. }
+            | Self::PushConstantMismatch { .. }
+            | Self::UnresolvedDescriptorCount { .. } => None,
+        }
+    }
+}
@@ -2,11 +2,53 @@
 //!
 //! A library for easy to way automatically create multiple shader stages from the directory path.
 
+mod device;
+mod error;
+mod loader;
+mod mock;
+mod naming;
+mod pipeline_layout;
+mod plan;
+mod reflection;
+mod scan;
+mod shader_set;
+mod source;
+mod specialization;
+mod spirv;
+mod stage_config;
+mod vertex_input;
+
+pub use device::{NoDevice, ShaderDevice};
+pub use error::ShaderCreatorError;
+pub use loader::ShaderRecord;
+pub use mock::{
+    MockDevice, RecordedDescriptorSetLayout, RecordedDescriptorSetLayoutBinding,
+    RecordedPipelineLayout, RecordedShaderModule,
+};
+pub use naming::{
+    default_naming_conventions, NamingConvention, ShaderName, SuffixConvention,
+    GLSL_STAGE_SUFFIXES, HLSL_STAGE_SUFFIXES,
+};
+pub use pipeline_layout::{BindingConfig, PipelineLayoutConfig, ProgramLayout};
+pub use plan::{
+    IgnoreReason, IgnoredFile, InvalidProgram, InvalidShader, PlannedShader, ShaderPlan,
+};
+pub use reflection::{
+    DescriptorBindingInfo, PushConstantBlockInfo, PushConstantMemberInfo, ShaderReflection,
+    SpecializationConstantInfo, SpecializationConstantType, VertexInputInfo,
+};
+pub use shader_set::{ShaderProgram, ShaderSet};
+pub use source::ShaderSource;
+pub use specialization::{SpecializationConstants, SpecializationValue};
+pub use stage_config::{StageConfig, StageSelector};
+pub use vertex_input::{VertexBindingAssignment, VertexInputConfig, VertexInputDescriptions};
+
+use loader::{LoadOptions, LoadedRecords};
+use scan::ScanFilter;
+use specialization::OwnedSpecializationInfo;
+
 use std::{
-    collections::HashMap,
     ffi::{c_void, CString},
-    fs::{read_dir, File},
-    io::Read,
     path::{Path, PathBuf},
     ptr,
 };
@@ -14,235 +56,941 @@ use std::{
 use ash::{
     vk::{
         AllocationCallbacks, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo,
-        ShaderModule, ShaderModuleCreateFlags, ShaderModuleCreateInfo, ShaderStageFlags,
-        SpecializationInfo, StructureType,
+        ShaderModuleCreateFlags, ShaderStageFlags, SpecializationInfo, StructureType,
     },
     Device,
 };
 
-pub struct ShaderStage<'a> {
-    pub device: &'a Device,
-    pub dir_path: &'a Path,
+/// Defines which source is used first to define the shader stage of the compiled shader,
+/// the other one is used as a fallback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StageDetection {
+    /// The execution model of the SPIR-V `OpEntryPoint`, falls back to the file name.
+    #[default]
+    SpirvFirst,
+    /// The naming conventions of the file name, falls back to the SPIR-V `OpEntryPoint`.
+    FileNameFirst,
+}
+
+/// Defines what happens when a compiled shader can't be read, validated, classified or compiled into a shader module.
+/// Errors of the directories, glob patterns and the main function name always fail the build.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// `build` returns the first error and destroys the shader modules it already created.
+    #[default]
+    FailFast,
+    /// `build` skips the failed compiled shaders and reports them in `ShaderSet::failures`,
+    /// e.g. for mod support and development builds.
+    Collect,
+}
+
+/// Defines what happens when several compiled shaders define the same stage of the same program,
+/// e.g. `sky.frag.spv` in two directories, or a module has several entry points of the same stage.
+/// Directories are scanned in order, sources go after them, entry points go in the module order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// `build` returns `ShaderCreatorError::DuplicateShader`, or `ShaderCreatorError::DuplicateEntryPoint`
+    /// for the entry points of one module.
+    #[default]
+    Error,
+    /// The first compiled shader is used, the others are ignored.
+    FirstWins,
+    /// The last compiled shader is used, e.g. to override shared shaders with the per-feature ones.
+    LastWins,
+}
+
+pub struct ShaderStage<'a, D: ShaderDevice = Device> {
+    pub device: &'a D,
+    pub dir_paths: Vec<&'a Path>,
+    pub sources: Vec<ShaderSource>,
+    pub recursive: bool,
+    pub skip_hidden: bool,
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
     pub shader_flags: ShaderModuleCreateFlags,
     pub shader_p_next: *const c_void,
-    pub main_function_name: CString,
+    pub main_function_name: Option<String>,
+    pub file_entry_points: Vec<(PathBuf, String)>,
+    pub stage_entry_points: Vec<(ShaderStageFlags, String)>,
     pub shader_stage_flags: PipelineShaderStageCreateFlags,
     pub shader_stage_p_next: *const c_void,
-    pub spec_info: *const SpecializationInfo,
+    spec_info: *const SpecializationInfo,
+    pub specialization_constants: Option<SpecializationConstants>,
+    pub stage_configs: Vec<(StageSelector, StageConfig)>,
+    pub stage_detection: StageDetection,
+    pub duplicate_policy: DuplicatePolicy,
+    pub error_policy: ErrorPolicy,
+    pub naming_conventions: Vec<Box<dyn NamingConvention>>,
+    pub destroy_on_drop: bool,
     allocation_callbacks: Option<&'a AllocationCallbacks>,
 }
 
-impl<'a> ShaderStage<'a> {
+impl<'a> ShaderStage<'a, NoDevice> {
+    /// Initiating the instance of ShaderStage struct without the device, only to `plan` the shader stages.
+    /// Directories and compiled shaders are added with `with_dir_path` and `with_source`.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::ShaderStage;
+    /// use std::path::Path;
+    /// # fn example() -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let plan = ShaderStage::without_device()
+    ///     .with_dir_path(Path::new("example_path/compiled_shaders"))
+    ///     .plan()?;
+    /// assert!(plan.is_valid());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn without_device() -> Self {
+        Self::init(&NoDevice, Vec::new(), Vec::new())
+    }
+}
+
+impl<'a, D: ShaderDevice> ShaderStage<'a, D> {
     /// Initiating the instance of ShaderStage struct, requires only the device and directory path, after that can be build shader stages.
     /// Can be customized flags and pointers to structs if it needed.
     /// # Examples
     ///
-    /// ```rust
-    /// use ash::{Device, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo};
+    /// ```rust,no_run
+    /// use ash::vk::PipelineShaderStageCreateFlags;
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
     /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
     ///
     /// let shader_stage_flags = PipelineShaderStageCreateFlags::RESERVED_2_NV | PipelineShaderStageCreateFlags::ALLOW_VARYING_SUBGROUP_SIZE_EXT;
-    /// let shader_stages_create_info: Vec<PipelineShaderStageCreateInfo> =
-    ///    ShaderStage::new(&device, &Path::new("example_path/compiled_shaders"))
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
     ///        .with_shader_stage_flags(shader_stage_flags)
-    ///        .build();
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(device: &'a D, dir_path: &'a Path) -> Self {
+        Self::init(device, vec![dir_path], Vec::new())
+    }
+
+    /// Initiating the instance of ShaderStage struct from multiple directories, they are scanned in the given order.
+    /// Compiled shaders that define the same stage of the same program are handled by `DuplicatePolicy`.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{DuplicatePolicy, ShaderSet, ShaderStage};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet = ShaderStage::from_dir_paths(
+    ///     device,
+    ///     &[Path::new("shaders/common"), Path::new("shaders/water")],
+    /// )
+    /// .with_duplicate_policy(DuplicatePolicy::LastWins)
+    /// .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_dir_paths(device: &'a D, dir_paths: &[&'a Path]) -> Self {
+        Self::init(device, dir_paths.to_vec(), Vec::new())
+    }
+
+    /// Initiating the instance of ShaderStage struct from the compiled shaders that are already in memory,
+    /// they go through the same shader module and shader stage creation as the compiled shaders from the directory.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash::vk::ShaderStageFlags;
+    /// use ash_shader_creator::{ShaderSet, ShaderSource, ShaderStage};
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    /// # let vertex_shader: &[u8] = &[];
+    /// # let fragment_shader: &[u8] = &[];
+    ///
+    /// let shader_set: ShaderSet = ShaderStage::from_sources(
+    ///     device,
+    ///     vec![
+    ///         ShaderSource::from_bytes("sky.vert.spv", None, vertex_shader),
+    ///         ShaderSource::from_bytes("sky", Some(ShaderStageFlags::FRAGMENT), fragment_shader),
+    ///     ],
+    /// )
+    /// .build()?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn new(device: &'a Device, dir_path: &'a Path) -> Self {
+    pub fn from_sources(device: &'a D, sources: Vec<ShaderSource>) -> Self {
+        Self::init(device, Vec::new(), sources)
+    }
+
+    fn init(device: &'a D, dir_paths: Vec<&'a Path>, sources: Vec<ShaderSource>) -> Self {
         Self {
             device,
-            dir_path,
+            dir_paths,
+            sources,
+            recursive: false,
+            skip_hidden: true,
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
             shader_flags: ShaderModuleCreateFlags::empty(),
             shader_p_next: ptr::null(),
             shader_stage_flags: PipelineShaderStageCreateFlags::empty(),
             shader_stage_p_next: ptr::null(),
             spec_info: ptr::null(),
-            main_function_name: CString::new("main").unwrap(),
+            specialization_constants: None,
+            stage_configs: Vec::new(),
+            stage_detection: StageDetection::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            error_policy: ErrorPolicy::default(),
+            naming_conventions: default_naming_conventions(),
+            destroy_on_drop: false,
+            main_function_name: None,
+            file_entry_points: Vec::new(),
+            stage_entry_points: Vec::new(),
             allocation_callbacks: None,
         }
     }
 
+    /// Adds the directory, scanned after the already added ones, to the `self.dir_paths` field.
+    pub fn with_dir_path(mut self, dir_path: &'a Path) -> Self {
+        self.dir_paths.push(dir_path);
+        self
+    }
+
+    /// Adds the compiled shader that is already in memory to the `self.sources` field.
+    pub fn with_source(mut self, source: ShaderSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Specifies whether subdirectories are scanned for the `self.recursive` field.
+    /// Compiled shaders in subdirectories belong to programs named after their relative path,
+    /// e.g. `post/bloom.frag.spv` belongs to the `post/bloom` program.
+    /// Symbolic links to the directories that contain them aren't followed, they are ignored with `IgnoreReason::Cycle`.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_recursive(true)
+    ///        .with_exclude_pattern("*.debug.spv")
+    ///        .with_exclude_pattern("legacy/**")
+    ///        .build()?;
+    /// let bloom_stages = shader_set.program("post/bloom");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Specifies whether files and subdirectories which names start with `.` are skipped for the `self.skip_hidden` field.
+    /// By default they are skipped.
+    pub fn with_skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Adds the glob pattern to the `self.include_patterns` field, if there are any, only matching files are loaded.
+    /// Patterns without `/` are matched against the file name, the others against the path relative to the scanned directory.
+    /// An invalid pattern is reported by `build` as `ShaderCreatorError::InvalidGlobPattern`.
+    pub fn with_include_pattern(mut self, pattern: &str) -> Self {
+        self.include_patterns.push(pattern.to_owned());
+        self
+    }
+
+    /// Adds the glob pattern to the `self.exclude_patterns` field, matching files and subdirectories are skipped.
+    /// Patterns are matched like in `with_include_pattern`.
+    pub fn with_exclude_pattern(mut self, pattern: &str) -> Self {
+        self.exclude_patterns.push(pattern.to_owned());
+        self
+    }
+
     /// Specifies `ShaderModuleCreateFlags` for the `self.shader_flags` field.
     /// # Examples
     ///
-    /// ```rust
-    /// use ash::{Device, ShaderModuleCreateFlags, PipelineShaderStageCreateInfo};
+    /// ```rust,no_run
+    /// use ash::vk::ShaderModuleCreateFlags;
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
     /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
     ///
     /// let shader_flags = ShaderModuleCreateFlags::RESERVED_0_NV;
-    /// let shader_stages_create_info: Vec<PipelineShaderStageCreateInfo> =
-    ///    ShaderStage::new(&device, &Path::new("example_path/compiled_shaders"))
-    ///        .with_shader_stage_flags(shader_flags)
-    ///        .build();
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_shader_flags(shader_flags)
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn with_shader_flags(&mut self, shader_flags: ShaderModuleCreateFlags) {
+    pub fn with_shader_flags(mut self, shader_flags: ShaderModuleCreateFlags) -> Self {
         self.shader_flags = shader_flags;
+        self
     }
 
     /// Specifies `pointer` to the struct for the `self.shader_p_next` field.
-    pub fn with_shader_p_next(&mut self, p_next: *const c_void) {
+    pub fn with_shader_p_next(mut self, p_next: *const c_void) -> Self {
         self.shader_p_next = p_next;
+        self
     }
 
     /// Specifies `PipelineShaderStageCreateFlags` for the `self.shader_stage_flags` field.
     /// # Examples
     ///
-    /// ```rust
-    /// use ash::{Device, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo};
+    /// ```rust,no_run
+    /// use ash::vk::PipelineShaderStageCreateFlags;
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
     /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
     ///
     /// let shader_stage_flags = PipelineShaderStageCreateFlags::RESERVED_2_NV | PipelineShaderStageCreateFlags::ALLOW_VARYING_SUBGROUP_SIZE_EXT;
-    /// let shader_stages_create_info: Vec<PipelineShaderStageCreateInfo> =
-    ///    ShaderStage::new(&device, &Path::new("example_path/compiled_shaders"))
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
     ///        .with_shader_stage_flags(shader_stage_flags)
-    ///        .build();
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn with_shader_stage_flags(&mut self, shader_stage_flags: PipelineShaderStageCreateFlags) {
+    pub fn with_shader_stage_flags(
+        mut self,
+        shader_stage_flags: PipelineShaderStageCreateFlags,
+    ) -> Self {
         self.shader_stage_flags = shader_stage_flags;
+        self
     }
 
     /// Specifies `pointer` to the struct for the `self.shader_stage_p_next` field.
-    pub fn with_shader_stage_p_next(&mut self, p_next: *const c_void) {
+    pub fn with_shader_stage_p_next(mut self, p_next: *const c_void) -> Self {
         self.shader_stage_p_next = p_next;
+        self
     }
 
     /// Specifies `SpecializationInfo` for the `self.spec_info` field.
-    pub fn with_spec_info(&mut self, spec_info: *const SpecializationInfo) {
+    /// Map entries and data of the `SpecializationInfo` are copied by `build`, so they have to live only until the build.
+    /// Prefer `with_specialization_constants`, which needs no pointers.
+    /// # Safety
+    ///
+    /// Until `build` is called, not null `spec_info` must point to the valid `SpecializationInfo`,
+    /// whose `p_map_entries` and `p_data` point to `map_entry_count` entries and `data_size` bytes.
+    pub unsafe fn with_spec_info(mut self, spec_info: *const SpecializationInfo) -> Self {
         self.spec_info = spec_info;
+        self
+    }
+
+    /// Specifies `SpecializationConstants` for the `self.specialization_constants` field.
+    /// They take precedence over `spec_info` and live as long as the built `ShaderSet`.
+    pub fn with_specialization_constants(
+        mut self,
+        specialization_constants: SpecializationConstants,
+    ) -> Self {
+        self.specialization_constants = Some(specialization_constants);
+        self
+    }
+
+    /// Adds the `StageConfig` for the stages selected by the `StageSelector` to the `self.stage_configs` field.
+    /// `shader_stage_flags`, `shader_stage_p_next`, `spec_info` and `specialization_constants` are the defaults
+    /// for the fields the configs don't set.
+    /// Specialization info of the configs is copied by `build` like the default one.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash::vk::{PipelineShaderStageCreateFlags, ShaderStageFlags};
+    /// use ash_shader_creator::{
+    ///     ShaderSet, ShaderStage, SpecializationConstants, StageConfig, StageSelector,
+    /// };
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_stage_config(
+    ///            StageSelector::Stage(ShaderStageFlags::COMPUTE),
+    ///            StageConfig::new()
+    ///                .with_flags(PipelineShaderStageCreateFlags::ALLOW_VARYING_SUBGROUP_SIZE_EXT),
+    ///        )
+    ///        .with_stage_config(
+    ///            StageSelector::Stage(ShaderStageFlags::FRAGMENT),
+    ///            StageConfig::new()
+    ///                .with_specialization_constants(SpecializationConstants::new().set(0, 64u32)),
+    ///        )
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_stage_config(mut self, selector: StageSelector, config: StageConfig) -> Self {
+        self.stage_configs.push((selector, config));
+        self
+    }
+
+    /// Specifies `StageDetection` for the `self.stage_detection` field.
+    /// By default the stage is defined from the SPIR-V `OpEntryPoint` and the file name is used only as a fallback.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderSet, ShaderStage, StageDetection};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_stage_detection(StageDetection::FileNameFirst)
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_stage_detection(mut self, stage_detection: StageDetection) -> Self {
+        self.stage_detection = stage_detection;
+        self
+    }
+
+    /// Adds the `NamingConvention` to the `self.naming_conventions` field, conventions are tried in order
+    /// and the first one that matches the file name defines its program name, stage and variant.
+    /// By default `SuffixConvention::glslang` and `SuffixConvention::dxc` are used.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderSet, ShaderStage, SuffixConvention};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// // `sky_vs.spv`, `sky_ps.spv`.
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_naming_convention(SuffixConvention::suffix_before_spv())
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_naming_convention<N: NamingConvention + 'static>(
+        mut self,
+        naming_convention: N,
+    ) -> Self {
+        self.naming_conventions.push(Box::new(naming_convention));
+        self
+    }
+
+    /// Replaces all naming conventions in the `self.naming_conventions` field.
+    pub fn with_naming_conventions(
+        mut self,
+        naming_conventions: Vec<Box<dyn NamingConvention>>,
+    ) -> Self {
+        self.naming_conventions = naming_conventions;
+        self
+    }
+
+    /// Specifies `DuplicatePolicy` for the `self.duplicate_policy` field.
+    /// By default compiled shaders that define the same stage of the same program are an error.
+    pub fn with_duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
+    }
+
+    /// Specifies `ErrorPolicy` for the `self.error_policy` field.
+    /// By default the first failed compiled shader fails the whole build.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ErrorPolicy, ShaderSet, ShaderStage};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/mods/shaders"))
+    ///        .with_error_policy(ErrorPolicy::Collect)
+    ///        .build()?;
+    /// for failure in shader_set.failures() {
+    ///     eprintln!("Skipped {:?}: {}", failure.path, failure.error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
     }
 
     /// Specifies `main function name` for the `self.main_function_name` field.
+    /// By default every stage uses the entry point name found in the `OpEntryPoint` of its module,
+    /// the main function name overrides it for every stage that has no file or stage override.
+    /// A name with an interior nul byte is reported by `build` as `ShaderCreatorError::InvalidMainFunctionName`.
     /// # Examples
     ///
-    /// ```rust
-    /// use ash::{Device, PipelineShaderStageCreateInfo};
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
     /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
     ///
-    /// let shader_stages_create_info: Vec<PipelineShaderStageCreateInfo> =
-    ///    ShaderStage::new(&device, &Path::new("example_path/compiled_shaders"))
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
     ///        .with_main_function_name("my_main_function")
-    ///        .build();
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn with_main_function_name(&mut self, main_function_name: &str) {
-        self.main_function_name = CString::new(main_function_name).unwrap();
+    pub fn with_main_function_name(mut self, main_function_name: &str) -> Self {
+        self.main_function_name = Some(main_function_name.to_owned());
+        self
+    }
+
+    /// Adds the entry point name of the compiled shader to the `self.file_entry_points` field.
+    /// `file_path` matches the paths of compiled shaders ending with it, e.g. `sky.vert.spv`,
+    /// or the name of the `ShaderSource`. The file override takes precedence over all other entry point names.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash::vk::ShaderStageFlags;
+    /// use ash_shader_creator::{ShaderSet, ShaderStage};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set: ShaderSet =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .with_stage_entry_point(ShaderStageFlags::VERTEX, "VSMain")
+    ///        .with_stage_entry_point(ShaderStageFlags::FRAGMENT, "PSMain")
+    ///        .with_file_entry_point(Path::new("sky.frag.spv"), "SkyMain")
+    ///        .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_file_entry_point(mut self, file_path: &Path, entry_point_name: &str) -> Self {
+        self.file_entry_points
+            .push((file_path.to_path_buf(), entry_point_name.to_owned()));
+        self
+    }
+
+    /// Adds the entry point name of every compiled shader of the stage to the `self.stage_entry_points` field.
+    /// The stage override takes precedence over the main function name and the SPIR-V entry point name.
+    pub fn with_stage_entry_point(
+        mut self,
+        stage: ShaderStageFlags,
+        entry_point_name: &str,
+    ) -> Self {
+        self.stage_entry_points
+            .push((stage, entry_point_name.to_owned()));
+        self
     }
 
     /// Specifies `AllocationCallbacks` for the creating shader modules for the `self.allocation_callbacks` field.
     pub fn with_allocation_callbacks(
-        &mut self,
+        mut self,
         allocation_callbacks: Option<&'a AllocationCallbacks>,
-    ) {
+    ) -> Self {
         self.allocation_callbacks = allocation_callbacks;
+        self
+    }
+
+    /// Specifies whether the built `ShaderSet` destroys its shader modules on drop for the `self.destroy_on_drop` field.
+    /// By default the modules have to be destroyed with `ShaderSet::destroy`.
+    pub fn with_destroy_on_drop(mut self, destroy_on_drop: bool) -> Self {
+        self.destroy_on_drop = destroy_on_drop;
+        self
     }
 
-    /// Consumes struct's `instance` and builds vector of shader stages.
+    /// Consumes struct's `instance` and builds the set of shader stages, which owns the created shader modules.
+    /// Stages are grouped into programs by the naming conventions of the compiled shader names, e.g. `sky.vert.spv`
+    /// and `sky.frag.spv` belong to the `sky` program, and sorted in the pipeline order inside every program.
+    /// # Errors
+    ///
+    /// Returns `ShaderCreatorError` if the directory or any compiled shader in it can't be read,
+    /// a shader module can't be created or the stage of a shader can't be defined.
+    /// The shader modules already created by this call are destroyed before the error is returned.
+    /// With `ErrorPolicy::Collect` only the errors of the directories are returned, the failed compiled shaders
+    /// are skipped and reported in `ShaderSet::failures`.
     /// # Examples
     ///
-    /// ```rust
-    /// use ash::{Device, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo};
+    /// ```rust,no_run
+    /// use ash::vk::PipelineShaderStageCreateInfo;
+    /// use ash_shader_creator::ShaderStage;
     /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
     ///
-    /// let shader_stages_create_info: Vec<PipelineShaderStageCreateInfo> =
-    ///    ShaderStage::new(&device, &Path::new("example_path/compiled_shaders"))
-    ///        .build();
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let shader_stages_create_info: &[PipelineShaderStageCreateInfo] = shader_set.stages();
+    /// // Create pipelines with `shader_stages_create_info`.
+    /// shader_set.destroy(device);
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn build(self) -> Vec<PipelineShaderStageCreateInfo> {
-        let shader_modules = create_shader_modules(
-            self.device,
-            self.dir_path,
-            self.shader_flags,
-            self.shader_p_next,
+    pub fn build(self) -> Result<ShaderSet<'a, D>, ShaderCreatorError> {
+        let (specialization_info, config_specialization_infos) = self.specialization_infos();
+
+        let LoadedRecords {
+            mut records,
+            mut failures,
+        } = self.load()?;
+        loader::sort_records(&mut records);
+
+        let mut stages = records
+            .iter()
+            .map(|record| {
+                self.resolve_stage(record, &specialization_info, &config_specialization_infos)
+            })
+            .collect::<Vec<_>>();
+        let specialization_errors = specialization_errors(&records, &stages);
+        if !specialization_errors.is_empty() {
+            if self.error_policy == ErrorPolicy::FailFast {
+                loader::destroy_shader_modules(self.device, &records, self.allocation_callbacks);
+                return Err(specialization_errors.into_iter().next().unwrap().1);
+            }
+
+            // Failed stages are removed from the end, so the indices stay valid.
+            let mut failed_records = Vec::new();
+            for (index, error) in specialization_errors.into_iter().rev() {
+                stages.remove(index);
+                failed_records.push(records.remove(index));
+                failures.push(InvalidShader {
+                    path: failed_records[failed_records.len() - 1].path.clone(),
+                    error,
+                });
+            }
+            // Modules shared with the stages that are left must stay alive.
+            failed_records
+                .retain(|failed| records.iter().all(|record| record.module != failed.module));
+            loader::destroy_shader_modules(self.device, &failed_records, self.allocation_callbacks);
+        }
+
+        let entry_point_names = records
+            .iter()
+            // Validated entry point names and names from SPIR-V literal strings can't contain nul.
+            .map(|record| {
+                CString::new(record.entry_point.as_str()).expect("Invalid entry point name")
+            })
+            .collect::<Vec<_>>();
+        let stages = records
+            .iter()
+            .zip(stages)
+            .zip(&entry_point_names)
+            .map(|((record, stage), entry_point_name)| stage.create_info(record, entry_point_name))
+            .collect();
+        let drop_device = if self.destroy_on_drop {
+            Some(self.device)
+        } else {
+            None
+        };
+
+        Ok(ShaderSet::new(
+            records,
+            stages,
+            failures,
+            entry_point_names,
+            specialization_info
+                .into_iter()
+                .chain(config_specialization_infos.into_iter().flatten())
+                .collect(),
             self.allocation_callbacks,
-        );
-
-        let file_paths = read_dir(self.dir_path)
-            .unwrap()
-            .into_iter()
-            .filter(|file_name| {
-                file_name
-                    .as_ref()
-                    .unwrap()
-                    .path()
-                    .to_str()
-                    .unwrap()
-                    .contains(".spv")
+            drop_device,
+        ))
+    }
+
+    /// Scans every directory once and creates a shader module for every compiled shader in them and in `self.sources`.
+    /// Every returned record keeps the path and the stage of the compiled shader together with the module created from it.
+    /// Unlike `build`, the caller is responsible for destroying the created shader modules.
+    /// # Errors
+    ///
+    /// Returns `ShaderCreatorError` if the directory or any compiled shader in it can't be read,
+    /// a shader module can't be created or the stage of a shader can't be defined.
+    /// The shader modules already created by this call are destroyed before the error is returned.
+    /// With `ErrorPolicy::Collect` the failed compiled shaders are skipped, use `build` or `plan` to get their errors.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderRecord, ShaderStage};
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let records: Vec<ShaderRecord> =
+    ///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+    ///        .load_records()?;
+    /// for record in &records {
+    ///     println!("{:?}: {:?}", record.path, record.stage);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_records(&self) -> Result<Vec<ShaderRecord>, ShaderCreatorError> {
+        self.load().map(|loaded_records| loaded_records.records)
+    }
+
+    fn load(&self) -> Result<LoadedRecords, ShaderCreatorError> {
+        self.validate_entry_point_names()?;
+        let scan_filter = self.scan_filter()?;
+        loader::create_shader_records(
+            self.device,
+            &self.dir_paths,
+            &self.sources,
+            &scan_filter,
+            &self.load_options(),
+        )
+    }
+
+    /// Performs the same directory scan, naming resolution, SPIR-V and specialization checks as `build`,
+    /// but creates no shader modules, so the device is never used and the stage can be created with `without_device`.
+    /// The returned plan lists the shaders `build` would create, the ignored files and the invalid shaders.
+    /// The descriptor bindings and push constant blocks of every program are merged like by
+    /// `ShaderProgram::create_pipeline_layout`, so their mismatches are reported as invalid programs.
+    /// # Errors
+    ///
+    /// Returns `ShaderCreatorError` if the directory can't be read or the glob pattern is invalid,
+    /// errors of the compiled shaders are reported in `ShaderPlan::invalid_shaders`
+    /// and errors of the program layouts in `ShaderPlan::invalid_programs`.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::ShaderStage;
+    /// use std::path::Path;
+    /// # fn example() -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let plan = ShaderStage::without_device()
+    ///     .with_dir_path(Path::new("example_path/compiled_shaders"))
+    ///     .with_recursive(true)
+    ///     .plan()?;
+    /// for shader in &plan.shaders {
+    ///     println!("{} {:?}: {:?}", shader.program_name, shader.stage, shader.path);
+    /// }
+    /// for invalid_shader in &plan.invalid_shaders {
+    ///     eprintln!("{}", invalid_shader.error);
+    /// }
+    /// for invalid_program in &plan.invalid_programs {
+    ///     eprintln!("{}", invalid_program.error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn plan(&self) -> Result<ShaderPlan, ShaderCreatorError> {
+        self.validate_entry_point_names()?;
+        let scan_filter = self.scan_filter()?;
+        let (mut plan, mut records) = loader::plan_shaders(
+            &self.dir_paths,
+            &self.sources,
+            &scan_filter,
+            &self.load_options(),
+        )?;
+
+        // Stages `build` would fail on or skip because of their specialization.
+        let (specialization_info, config_specialization_infos) = self.specialization_infos();
+        let stages = records
+            .iter()
+            .map(|record| {
+                self.resolve_stage(record, &specialization_info, &config_specialization_infos)
             })
-            .map(|path| path.unwrap().path());
+            .collect::<Vec<_>>();
+        let specialization_errors = specialization_errors(&records, &stages);
+        for (index, error) in specialization_errors.into_iter().rev() {
+            plan.shaders.remove(index);
+            let record = records.remove(index);
+            plan.invalid_shaders.push(InvalidShader {
+                path: record.path,
+                error,
+            });
+        }
+
+        // Programs whose layouts would fail to be reflected.
+        let mut start = 0;
+        while start < records.len() {
+            let first = &records[start];
+            let len = records[start..]
+                .iter()
+                .take_while(|record| {
+                    record.program_name == first.program_name && record.variant == first.variant
+                })
+                .count();
+            let program_records = &records[start..start + len];
+            let layout_error = reflection::descriptor_set_bindings(program_records)
+                .and_then(|_| reflection::push_constant_ranges(program_records))
+                .err();
+            if let Some(error) = layout_error {
+                plan.invalid_programs.push(InvalidProgram {
+                    program_name: first.program_name.clone(),
+                    variant: first.variant.clone(),
+                    error,
+                });
+            }
+            start += len;
+        }
+
+        Ok(plan)
+    }
+
+    /// Consumes struct's `instance` and builds the set of shader stages like `build`, but panics on any error.
+    pub fn build_or_panic(self) -> ShaderSet<'a, D> {
+        self.build()
+            .unwrap_or_else(|error| panic!("Failed to build shader stages: {}", error))
+    }
+
+    fn load_options(&self) -> LoadOptions<'_> {
+        LoadOptions {
+            flags: self.shader_flags,
+            p_next: self.shader_p_next,
+            allocation_callbacks: self.allocation_callbacks,
+            stage_detection: self.stage_detection,
+            duplicate_policy: self.duplicate_policy,
+            error_policy: self.error_policy,
+            naming_conventions: &self.naming_conventions,
+            main_function_name: self.main_function_name.as_deref(),
+            file_entry_points: &self.file_entry_points,
+            stage_entry_points: &self.stage_entry_points,
+        }
+    }
 
-        let shader_path: HashMap<&ShaderModule, PathBuf> =
-            shader_modules.iter().zip(file_paths.into_iter()).collect();
+    fn scan_filter(&self) -> Result<ScanFilter, ShaderCreatorError> {
+        ScanFilter::new(
+            self.recursive,
+            self.skip_hidden,
+            &self.include_patterns,
+            &self.exclude_patterns,
+        )
+    }
 
-        shader_modules
+    /// Checks the main function name and the entry point overrides before any shader module is created.
+    fn validate_entry_point_names(&self) -> Result<(), ShaderCreatorError> {
+        let file_entry_points = self.file_entry_points.iter().map(|(_, name)| name);
+        let stage_entry_points = self.stage_entry_points.iter().map(|(_, name)| name);
+        self.main_function_name
             .iter()
-            .map(|module| {
-                let path = shader_path.get(&module).unwrap().to_str().unwrap();
-
-                PipelineShaderStageCreateInfo {
-                    s_type: StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
-                    p_next: self.shader_stage_p_next,
-                    flags: self.shader_stage_flags,
-                    stage: if path.contains(".vert.spv") || path.contains(".vs") {
-                        ShaderStageFlags::VERTEX
-                    } else if path.contains(".frag.spv") || path.contains(".fs") {
-                        ShaderStageFlags::FRAGMENT
-                    } else {
-                        panic!("Failed to define shader type!")
-                    },
-                    module: *module,
-                    p_name: self.main_function_name.as_ptr(),
-                    p_specialization_info: self.spec_info,
-                }
+            .chain(file_entry_points)
+            .chain(stage_entry_points)
+            .try_for_each(|name| {
+                CString::new(name.as_str()).map(drop).map_err(|source| {
+                    ShaderCreatorError::InvalidMainFunctionName {
+                        name: name.clone(),
+                        source,
+                    }
+                })
             })
-            .collect()
     }
-}
 
-fn create_shader_modules(
-    device: &Device,
-    dir_path: &Path,
-    flags: ShaderModuleCreateFlags,
-    p_next: *const c_void,
-    allocation_callbacks: Option<&AllocationCallbacks>,
-) -> Vec<ShaderModule> {
-    let compiled_shader_path = read_dir(dir_path)
-        .unwrap_or_else(|_| panic!("Failed to read directory path at {:?}", dir_path));
-    let files_path_buf: Vec<PathBuf> = compiled_shader_path
-        .into_iter()
-        .filter(|file_name| {
-            let file_name = file_name
-                .as_ref()
-                .unwrap()
-                .path()
-                .to_str()
-                .unwrap()
-                .to_owned();
-
-            file_name.contains(".spv") || file_name.contains(".vs") || file_name.contains(".fs")
-        })
-        .map(|compiled_shader| compiled_shader.unwrap().path())
-        .collect();
-
-    let files = files_path_buf.iter().map(|path_buf| {
-        File::open(path_buf)
-            .unwrap_or_else(|_| panic!("Failed to open compiled shader file at {:?}", path_buf))
-    });
-
-    files
-        .map(|file| {
-            let shader_code: Vec<u8> = file.bytes().filter_map(|byte| byte.ok()).collect();
-
-            let shader_module_create_info = ShaderModuleCreateInfo {
-                s_type: StructureType::SHADER_MODULE_CREATE_INFO,
-                p_next,
-                flags,
-                code_size: shader_code.len(),
-                p_code: shader_code.as_ptr() as *const u32,
-            };
+    /// Owned specialization info of the defaults and of every stage config, in the order of the configs.
+    fn specialization_infos(
+        &self,
+    ) -> (
+        Option<OwnedSpecializationInfo>,
+        Vec<Option<OwnedSpecializationInfo>>,
+    ) {
+        // `with_spec_info` callers guarantee the pointers are valid until the build.
+        let specialization_info = match &self.specialization_constants {
+            Some(specialization_constants) => Some(specialization_constants.to_owned_info()),
+            None => unsafe { copy_specialization_info(self.spec_info) },
+        };
+        let config_specialization_infos = self
+            .stage_configs
+            .iter()
+            .map(
+                |(_, config)| match (&config.specialization_constants, config.spec_info()) {
+                    (Some(specialization_constants), _) => {
+                        Some(specialization_constants.to_owned_info())
+                    }
+                    (None, Some(spec_info)) => unsafe { copy_specialization_info(spec_info) },
+                    (None, None) => None,
+                },
+            )
+            .collect();
 
-            unsafe {
-                device
-                    .create_shader_module(&shader_module_create_info, allocation_callbacks)
-                    .expect("Failed to create shader module!")
+        (specialization_info, config_specialization_infos)
+    }
+
+    /// Applies the stage configs that select the stage over the defaults.
+    fn resolve_stage<'s>(
+        &'s self,
+        record: &ShaderRecord,
+        specialization_info: &'s Option<OwnedSpecializationInfo>,
+        config_specialization_infos: &'s [Option<OwnedSpecializationInfo>],
+    ) -> ResolvedStage<'s> {
+        let mut stage = ResolvedStage {
+            flags: self.shader_stage_flags,
+            p_next: self.shader_stage_p_next,
+            specialization_info: specialization_info.as_ref(),
+            specialization_constants: self.specialization_constants.as_ref(),
+            default_specialization: true,
+        };
+
+        let mut configs: Vec<_> = self
+            .stage_configs
+            .iter()
+            .zip(config_specialization_infos)
+            .filter(|((selector, _), _)| selector.matches(record))
+            .collect();
+        configs.sort_by_key(|((selector, _), _)| selector.precedence());
+        for ((_, config), config_specialization_info) in configs {
+            if let Some(flags) = config.flags {
+                stage.flags = flags;
+            }
+            if let Some(p_next) = config.p_next {
+                stage.p_next = p_next;
+            }
+            if config.has_specialization() {
+                stage.specialization_info = config_specialization_info.as_ref();
+                stage.specialization_constants = config.specialization_constants.as_ref();
+                stage.default_specialization = false;
             }
+        }
+
+        stage
+    }
+}
+
+/// Validates the specialization of every stage, returns the errors with the indices of their stages.
+fn specialization_errors(
+    records: &[ShaderRecord],
+    stages: &[ResolvedStage],
+) -> Vec<(usize, ShaderCreatorError)> {
+    records
+        .iter()
+        .zip(stages)
+        .enumerate()
+        .filter_map(|(index, (record, stage))| {
+            let specialization_info = stage.specialization_info?;
+            let program_records = if stage.default_specialization {
+                Some(records)
+            } else {
+                None
+            };
+            specialization_info
+                .validate(stage.specialization_constants, record, program_records)
+                .err()
+                .map(|error| (index, error))
         })
-        .collect::<Vec<ShaderModule>>()
+        .collect()
+}
+
+/// Fields of the shader stage resolved from the defaults of `ShaderStage` and the stage configs.
+struct ResolvedStage<'s> {
+    flags: PipelineShaderStageCreateFlags,
+    p_next: *const c_void,
+    specialization_info: Option<&'s OwnedSpecializationInfo>,
+    /// Constants `specialization_info` was packed from, `None` for the copied `SpecializationInfo`.
+    specialization_constants: Option<&'s SpecializationConstants>,
+    /// Whether `specialization_info` is the default of every stage rather than the one of a stage config.
+    default_specialization: bool,
+}
+
+impl ResolvedStage<'_> {
+    fn create_info(
+        &self,
+        record: &ShaderRecord,
+        entry_point_name: &CString,
+    ) -> PipelineShaderStageCreateInfo {
+        PipelineShaderStageCreateInfo {
+            s_type: StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+            p_next: self.p_next,
+            flags: self.flags,
+            stage: record.stage,
+            module: record.module,
+            p_name: entry_point_name.as_ptr(),
+            p_specialization_info: self
+                .specialization_info
+                .map_or(ptr::null(), OwnedSpecializationInfo::as_ptr),
+        }
+    }
+}
+
+/// Copies the specialization info, `None` for the null pointer.
+/// # Safety
+///
+/// Not null `spec_info` must point to the valid `SpecializationInfo`, which `with_spec_info` callers guarantee.
+unsafe fn copy_specialization_info(
+    spec_info: *const SpecializationInfo,
+) -> Option<OwnedSpecializationInfo> {
+    spec_info
+        .as_ref()
+        .map(|spec_info| OwnedSpecializationInfo::copy_from(spec_info))
 }
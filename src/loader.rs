@@ -0,0 +1,502 @@
+//! Loading of the compiled shaders and creating of the shader modules from them.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::c_void,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::Read,
+    mem,
+    path::{Path, PathBuf},
+};
+
+use ash::vk::{
+    AllocationCallbacks, ShaderModule, ShaderModuleCreateFlags, ShaderModuleCreateInfo,
+    ShaderStageFlags, StructureType,
+};
+
+use crate::{
+    naming::{self, FileNaming},
+    reflection,
+    scan::ScanFilter,
+    spirv, DuplicatePolicy, ErrorPolicy, IgnoredFile, InvalidShader, NamingConvention,
+    PlannedShader, ShaderCreatorError, ShaderDevice, ShaderPlan, ShaderReflection, ShaderSource,
+    StageDetection,
+};
+
+/// The compiled shader and the shader module created from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderRecord {
+    /// Path of the compiled shader file, or the name of the `ShaderSource`.
+    pub path: PathBuf,
+    /// Name of the program the shader belongs to, defined by the `NamingConvention`.
+    pub program_name: String,
+    /// Variant of the program the shader belongs to, defined by the `NamingConvention`.
+    pub variant: Option<String>,
+    /// Stage of the shader.
+    pub stage: ShaderStageFlags,
+    /// Name of the entry point the stage uses, found in the `OpEntryPoint` of the module
+    /// unless it's overridden by `ShaderStage`.
+    pub entry_point: String,
+    /// Shader module created from the compiled shader, shared by all stages of the module with several entry points.
+    pub module: ShaderModule,
+    /// Hash of the shader code, stays the same while the compiled shader isn't changed.
+    pub code_hash: u64,
+    /// Interface of the module reflected from the SPIR-V.
+    pub reflection: ShaderReflection,
+}
+
+/// Entry point of the modules without `OpEntryPoint`.
+const DEFAULT_ENTRY_POINT: &str = "main";
+
+/// Options of the shader modules creation shared by all compiled shaders.
+pub(crate) struct LoadOptions<'a> {
+    pub(crate) flags: ShaderModuleCreateFlags,
+    pub(crate) p_next: *const c_void,
+    pub(crate) allocation_callbacks: Option<&'a AllocationCallbacks>,
+    pub(crate) stage_detection: StageDetection,
+    pub(crate) duplicate_policy: DuplicatePolicy,
+    pub(crate) error_policy: ErrorPolicy,
+    pub(crate) naming_conventions: &'a [Box<dyn NamingConvention>],
+    pub(crate) main_function_name: Option<&'a str>,
+    pub(crate) file_entry_points: &'a [(PathBuf, String)],
+    pub(crate) stage_entry_points: &'a [(ShaderStageFlags, String)],
+}
+
+impl LoadOptions<'_> {
+    /// Overrides for the file go first, then for the stage, then the main function name,
+    /// the last added override wins. Without overrides the entry point of the module is used.
+    fn entry_point(
+        &self,
+        path: &Path,
+        stage: ShaderStageFlags,
+        spirv_entry_point: Option<String>,
+    ) -> String {
+        let file_entry_point = self
+            .file_entry_points
+            .iter()
+            .rev()
+            .find(|(file_path, _)| path.ends_with(file_path))
+            .map(|(_, name)| name);
+        let stage_entry_point = self
+            .stage_entry_points
+            .iter()
+            .rev()
+            .find(|(entry_point_stage, _)| *entry_point_stage == stage)
+            .map(|(_, name)| name);
+
+        file_entry_point
+            .or(stage_entry_point)
+            .map(String::as_str)
+            .or(self.main_function_name)
+            .map(str::to_owned)
+            .or(spirv_entry_point)
+            .unwrap_or_else(|| String::from(DEFAULT_ENTRY_POINT))
+    }
+}
+
+/// The compiled shader that is read and classified, but has no shader module yet.
+struct LoadedShader {
+    path: PathBuf,
+    program_name: String,
+    variant: Option<String>,
+    stages: Vec<LoadedStage>,
+    code: Vec<u32>,
+}
+
+/// The stage of the loaded shader, one for every entry point of the module.
+#[derive(PartialEq)]
+struct LoadedStage {
+    stage: ShaderStageFlags,
+    entry_point: String,
+    reflection: ShaderReflection,
+}
+
+/// Compiled shaders found by the scan, every one is either loaded or failed to load.
+struct Discovery {
+    shaders: Vec<Result<LoadedShader, ShaderCreatorError>>,
+    ignored_files: Vec<IgnoredFile>,
+}
+
+/// Shader records created by `create_shader_records` and the compiled shaders that failed
+/// with `ErrorPolicy::Collect`.
+pub(crate) struct LoadedRecords {
+    pub(crate) records: Vec<ShaderRecord>,
+    pub(crate) failures: Vec<InvalidShader>,
+}
+
+/// Scans every directory once, in order, and creates a shader module for every compiled shader in them
+/// and for every source. Every compiled shader is read and validated before any shader module is created.
+/// With `ErrorPolicy::FailFast` the first error is returned, and if one of the modules can't be created,
+/// the already created ones are destroyed. With `ErrorPolicy::Collect` the failed shaders are skipped.
+pub(crate) fn create_shader_records<D: ShaderDevice>(
+    device: &D,
+    dir_paths: &[&Path],
+    sources: &[ShaderSource],
+    scan_filter: &ScanFilter,
+    options: &LoadOptions,
+) -> Result<LoadedRecords, ShaderCreatorError> {
+    let discovery = discover_shaders(dir_paths, sources, scan_filter, options)?;
+    let (shaders, mut failures) = split_failures(discovery.shaders);
+    let (shaders, duplicate_errors) = resolve_duplicates(shaders, options.duplicate_policy);
+    failures.extend(duplicate_errors.into_iter().map(invalid_shader));
+    if options.error_policy == ErrorPolicy::FailFast && !failures.is_empty() {
+        return Err(failures.remove(0).error);
+    }
+
+    let mut records = Vec::with_capacity(shaders.len());
+    for shader in shaders {
+        match create_shader_records_of_module(device, shader, options) {
+            Ok(module_records) => records.extend(module_records),
+            Err(error) if options.error_policy == ErrorPolicy::Collect => {
+                failures.push(invalid_shader(error))
+            }
+            Err(error) => {
+                // Rolls back the failed creation, so no shader module of it is leaked on the device.
+                destroy_shader_modules(device, &records, options.allocation_callbacks);
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(LoadedRecords { records, failures })
+}
+
+/// Destroys the shader modules of the records, every module once even if several stages share it.
+pub(crate) fn destroy_shader_modules<D: ShaderDevice>(
+    device: &D,
+    records: &[ShaderRecord],
+    allocation_callbacks: Option<&AllocationCallbacks>,
+) {
+    let mut modules: Vec<ShaderModule> = Vec::with_capacity(records.len());
+    for record in records {
+        if !modules.contains(&record.module) {
+            modules.push(record.module);
+        }
+    }
+
+    for module in modules {
+        unsafe { device.destroy_shader_module(module, allocation_callbacks) };
+    }
+}
+
+/// Scans every directory and loads every source like `create_shader_records`, but instead of creating
+/// the shader modules reports which shaders would be created, ignored or fail.
+/// The planned shaders are returned with their records too, whose modules are null, sorted like the shaders.
+pub(crate) fn plan_shaders(
+    dir_paths: &[&Path],
+    sources: &[ShaderSource],
+    scan_filter: &ScanFilter,
+    options: &LoadOptions,
+) -> Result<(ShaderPlan, Vec<ShaderRecord>), ShaderCreatorError> {
+    let discovery = discover_shaders(dir_paths, sources, scan_filter, options)?;
+    let (shaders, mut invalid_shaders) = split_failures(discovery.shaders);
+    let (shaders, duplicate_errors) = resolve_duplicates(shaders, options.duplicate_policy);
+    invalid_shaders.extend(duplicate_errors.into_iter().map(invalid_shader));
+
+    let mut records: Vec<ShaderRecord> = shaders
+        .into_iter()
+        .flat_map(|shader| shader_records(shader, ShaderModule::null()))
+        .collect();
+    sort_records(&mut records);
+    let shaders = records
+        .iter()
+        .map(|record| PlannedShader {
+            path: record.path.clone(),
+            program_name: record.program_name.clone(),
+            variant: record.variant.clone(),
+            stage: record.stage,
+            entry_point: record.entry_point.clone(),
+        })
+        .collect();
+
+    let plan = ShaderPlan {
+        shaders,
+        ignored_files: discovery.ignored_files,
+        invalid_shaders,
+        invalid_programs: Vec::new(),
+    };
+    Ok((plan, records))
+}
+
+/// Sorts the records by the program and its variant, and by the pipeline order inside every program.
+pub(crate) fn sort_records(records: &mut [ShaderRecord]) {
+    records.sort_by(|a, b| {
+        (&a.program_name, &a.variant, naming::stage_order(a.stage)).cmp(&(
+            &b.program_name,
+            &b.variant,
+            naming::stage_order(b.stage),
+        ))
+    });
+}
+
+/// Scans the directories and reads the sources. Errors of the directories fail the whole discovery,
+/// errors of the compiled shaders and of the files with non-UTF-8 names are kept with the shaders.
+fn discover_shaders(
+    dir_paths: &[&Path],
+    sources: &[ShaderSource],
+    scan_filter: &ScanFilter,
+    options: &LoadOptions,
+) -> Result<Discovery, ShaderCreatorError> {
+    let mut discovery = Discovery {
+        shaders: Vec::new(),
+        ignored_files: Vec::new(),
+    };
+
+    for dir_path in dir_paths {
+        let scan_result = scan_filter.scan_dir(dir_path, options.naming_conventions)?;
+        discovery.ignored_files.extend(scan_result.ignored_files);
+        discovery
+            .shaders
+            .extend(scan_result.failures.into_iter().map(Err));
+        for file in scan_result.shader_files {
+            let shader = read_shader_file(&file.path)
+                .and_then(|code| load_shader(file.path, file.naming, code, None, options));
+            discovery.shaders.push(shader);
+        }
+    }
+
+    for source in sources {
+        let path = PathBuf::from(&source.name);
+        let naming =
+            naming::resolve(options.naming_conventions, &path).unwrap_or_else(|| FileNaming {
+                program_name: source.name.clone(),
+                variant: None,
+                stage: None,
+            });
+        let shader = source
+            .words()
+            .and_then(|code| load_shader(path, naming, code, source.stage_hint, options));
+        discovery.shaders.push(shader);
+    }
+
+    Ok(discovery)
+}
+
+/// Defines the stages of the validated SPIR-V words, one for every `OpEntryPoint` with a Vulkan shader stage.
+/// Several entry points of the same stage are all kept, `resolve_duplicates` applies the `DuplicatePolicy` to them.
+/// `stage_hint` takes place of the stage defined from the naming conventions, with `StageDetection::FileNameFirst`
+/// it's the only stage of the module.
+fn load_shader(
+    path: PathBuf,
+    naming: FileNaming,
+    code: Vec<u32>,
+    stage_hint: Option<ShaderStageFlags>,
+    options: &LoadOptions,
+) -> Result<LoadedShader, ShaderCreatorError> {
+    let entry_points = match spirv::entry_points(&code) {
+        Some(entry_points) => entry_points,
+        None => return Err(ShaderCreatorError::MalformedSpirv { path }),
+    };
+    let spirv_stages: Vec<(ShaderStageFlags, String)> = entry_points
+        .iter()
+        .filter_map(|entry_point| Some((entry_point.stage()?, entry_point.name.clone())))
+        .collect();
+
+    // The stage may come from the file name, so the entry point of that stage is preferred over the first one.
+    let file_name_stage = |stage: ShaderStageFlags| {
+        let entry_point = entry_points
+            .iter()
+            .find(|entry_point| entry_point.stage() == Some(stage))
+            .or_else(|| entry_points.first())
+            .map(|entry_point| entry_point.name.clone());
+        vec![(stage, entry_point)]
+    };
+    let stages = match (options.stage_detection, stage_hint.or(naming.stage)) {
+        (StageDetection::FileNameFirst, Some(stage)) => file_name_stage(stage),
+        _ if !spirv_stages.is_empty() => spirv_stages
+            .into_iter()
+            .map(|(stage, entry_point)| (stage, Some(entry_point)))
+            .collect(),
+        (_, Some(stage)) => file_name_stage(stage),
+        (_, None) => return Err(ShaderCreatorError::UnknownStage { path }),
+    };
+
+    // Overrides of the entry point name make the entry points of the same stage identical.
+    let mut loaded_stages: Vec<LoadedStage> = Vec::with_capacity(stages.len());
+    for (stage, spirv_entry_point) in stages {
+        let reflection = match reflection::reflect(&code, stage) {
+            Some(reflection) => reflection,
+            None => return Err(ShaderCreatorError::MalformedSpirv { path }),
+        };
+        let loaded_stage = LoadedStage {
+            stage,
+            entry_point: options.entry_point(&path, stage, spirv_entry_point),
+            reflection,
+        };
+        if !loaded_stages.contains(&loaded_stage) {
+            loaded_stages.push(loaded_stage);
+        }
+    }
+
+    Ok(LoadedShader {
+        path,
+        program_name: naming.program_name,
+        variant: naming.variant,
+        stages: loaded_stages,
+        code,
+    })
+}
+
+/// Applies the `DuplicatePolicy` to the stages with the same program, variant and stage.
+/// Entry points of the same stage in one module are duplicates as well.
+/// With `DuplicatePolicy::Error` the first stage is kept and an error is returned for every duplicate.
+/// Shaders without stages left aren't returned.
+fn resolve_duplicates(
+    shaders: Vec<LoadedShader>,
+    duplicate_policy: DuplicatePolicy,
+) -> (Vec<LoadedShader>, Vec<ShaderCreatorError>) {
+    let mut resolved: Vec<LoadedShader> = Vec::with_capacity(shaders.len());
+    let mut errors = Vec::new();
+    for mut shader in shaders {
+        for loaded_stage in mem::take(&mut shader.stages) {
+            if let Some(module_stage) = shader
+                .stages
+                .iter_mut()
+                .find(|module_stage| module_stage.stage == loaded_stage.stage)
+            {
+                match duplicate_policy {
+                    DuplicatePolicy::Error => {
+                        errors.push(ShaderCreatorError::DuplicateEntryPoint {
+                            path: shader.path.clone(),
+                            stage: loaded_stage.stage,
+                            first_entry_point: module_stage.entry_point.clone(),
+                            second_entry_point: loaded_stage.entry_point,
+                        })
+                    }
+                    DuplicatePolicy::FirstWins => (),
+                    DuplicatePolicy::LastWins => *module_stage = loaded_stage,
+                }
+                continue;
+            }
+
+            let duplicate = resolved.iter_mut().find(|resolved_shader| {
+                resolved_shader.program_name == shader.program_name
+                    && resolved_shader.variant == shader.variant
+                    && resolved_shader
+                        .stages
+                        .iter()
+                        .any(|resolved_stage| resolved_stage.stage == loaded_stage.stage)
+            });
+
+            match (duplicate, duplicate_policy) {
+                (None, _) => shader.stages.push(loaded_stage),
+                (Some(resolved_shader), DuplicatePolicy::Error) => {
+                    errors.push(ShaderCreatorError::DuplicateShader {
+                        program_name: shader.program_name.clone(),
+                        variant: shader.variant.clone(),
+                        stage: loaded_stage.stage,
+                        first_path: resolved_shader.path.clone(),
+                        second_path: shader.path.clone(),
+                    })
+                }
+                (Some(_), DuplicatePolicy::FirstWins) => (),
+                (Some(resolved_shader), DuplicatePolicy::LastWins) => {
+                    resolved_shader
+                        .stages
+                        .retain(|resolved_stage| resolved_stage.stage != loaded_stage.stage);
+                    shader.stages.push(loaded_stage);
+                }
+            }
+        }
+        resolved.push(shader);
+    }
+    resolved.retain(|shader| !shader.stages.is_empty());
+
+    (resolved, errors)
+}
+
+/// Splits the loaded shaders from the failed ones, keeping the order of both.
+fn split_failures(
+    shaders: Vec<Result<LoadedShader, ShaderCreatorError>>,
+) -> (Vec<LoadedShader>, Vec<InvalidShader>) {
+    let mut loaded_shaders = Vec::new();
+    let mut failures = Vec::new();
+    for shader in shaders {
+        match shader {
+            Ok(shader) => loaded_shaders.push(shader),
+            Err(error) => failures.push(invalid_shader(error)),
+        }
+    }
+
+    (loaded_shaders, failures)
+}
+
+fn invalid_shader(error: ShaderCreatorError) -> InvalidShader {
+    InvalidShader {
+        path: error.path().map(Path::to_path_buf).unwrap_or_default(),
+        error,
+    }
+}
+
+/// Creates the shader module from the loaded shader and a record for every its stage.
+fn create_shader_records_of_module<D: ShaderDevice>(
+    device: &D,
+    shader: LoadedShader,
+    options: &LoadOptions,
+) -> Result<Vec<ShaderRecord>, ShaderCreatorError> {
+    let shader_module_create_info = ShaderModuleCreateInfo {
+        s_type: StructureType::SHADER_MODULE_CREATE_INFO,
+        p_next: options.p_next,
+        flags: options.flags,
+        code_size: shader.code.len() * 4,
+        p_code: shader.code.as_ptr(),
+    };
+
+    let module = unsafe {
+        device.create_shader_module(&shader_module_create_info, options.allocation_callbacks)
+    }
+    .map_err(|result| ShaderCreatorError::CreateShaderModule {
+        path: shader.path.clone(),
+        result,
+    })?;
+
+    Ok(shader_records(shader, module))
+}
+
+/// A record for every stage of the loaded shader, all with the same module.
+fn shader_records(shader: LoadedShader, module: ShaderModule) -> Vec<ShaderRecord> {
+    let mut hasher = DefaultHasher::new();
+    shader.code.hash(&mut hasher);
+    let code_hash = hasher.finish();
+
+    let LoadedShader {
+        path,
+        program_name,
+        variant,
+        stages,
+        ..
+    } = shader;
+
+    stages
+        .into_iter()
+        .map(|loaded_stage| ShaderRecord {
+            path: path.clone(),
+            program_name: program_name.clone(),
+            variant: variant.clone(),
+            stage: loaded_stage.stage,
+            entry_point: loaded_stage.entry_point,
+            module,
+            code_hash,
+            reflection: loaded_stage.reflection,
+        })
+        .collect()
+}
+
+/// Reads the compiled shader file as the validated SPIR-V words.
+fn read_shader_file(path: &Path) -> Result<Vec<u32>, ShaderCreatorError> {
+    let mut file = File::open(path).map_err(|source| ShaderCreatorError::OpenFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut shader_code = Vec::new();
+    file.read_to_end(&mut shader_code)
+        .map_err(|source| ShaderCreatorError::ReadFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    spirv::words_from_bytes(&shader_code)
+        .map_err(|invalid_spirv| invalid_spirv.into_error(path.to_path_buf()))
+}
@@ -0,0 +1,369 @@
+//! The recording device for testing the shader stages creation without a driver.
+
+use std::{cell::RefCell, ffi::c_void, slice};
+
+use ash::vk::{
+    self, AllocationCallbacks, BaseInStructure, DescriptorBindingFlags, DescriptorSetLayout,
+    DescriptorSetLayoutBindingFlagsCreateInfo, DescriptorSetLayoutCreateFlags,
+    DescriptorSetLayoutCreateInfo, DescriptorType, Handle, PipelineLayout,
+    PipelineLayoutCreateInfo, PushConstantRange, Sampler, ShaderModule, ShaderModuleCreateFlags,
+    ShaderModuleCreateInfo, ShaderStageFlags, StructureType,
+};
+
+use crate::ShaderDevice;
+
+/// Copy of the `ShaderModuleCreateInfo` the `MockDevice` received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedShaderModule {
+    /// The fabricated handle returned for the create info.
+    pub module: ShaderModule,
+    pub flags: ShaderModuleCreateFlags,
+    pub p_next: *const c_void,
+    /// SPIR-V words `p_code` pointed to.
+    pub code: Vec<u32>,
+    /// Whether the module was created with `AllocationCallbacks`.
+    pub with_allocation_callbacks: bool,
+    /// Whether the module was destroyed with `AllocationCallbacks`, `None` while the module isn't destroyed.
+    pub destroyed_with_allocation_callbacks: Option<bool>,
+}
+
+/// Copy of the `DescriptorSetLayoutCreateInfo` the `MockDevice` received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedDescriptorSetLayout {
+    /// The fabricated handle returned for the create info.
+    pub layout: DescriptorSetLayout,
+    pub flags: DescriptorSetLayoutCreateFlags,
+    pub bindings: Vec<RecordedDescriptorSetLayoutBinding>,
+    /// Whether the layout was created with `AllocationCallbacks`.
+    pub with_allocation_callbacks: bool,
+    /// Whether the layout was destroyed with `AllocationCallbacks`, `None` while the layout isn't destroyed.
+    pub destroyed_with_allocation_callbacks: Option<bool>,
+}
+
+/// Copy of the `DescriptorSetLayoutBinding` the `MockDevice` received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedDescriptorSetLayoutBinding {
+    pub binding: u32,
+    pub descriptor_type: DescriptorType,
+    pub descriptor_count: u32,
+    pub stage_flags: ShaderStageFlags,
+    /// Samplers `p_immutable_samplers` pointed to.
+    pub immutable_samplers: Vec<Sampler>,
+    /// Flags of the binding from the chained `DescriptorSetLayoutBindingFlagsCreateInfo`, empty without it.
+    pub binding_flags: DescriptorBindingFlags,
+}
+
+/// Copy of the `PipelineLayoutCreateInfo` the `MockDevice` received.
+#[derive(Debug, Clone)]
+pub struct RecordedPipelineLayout {
+    /// The fabricated handle returned for the create info.
+    pub layout: PipelineLayout,
+    pub set_layouts: Vec<DescriptorSetLayout>,
+    pub push_constant_ranges: Vec<PushConstantRange>,
+    /// Whether the layout was created with `AllocationCallbacks`.
+    pub with_allocation_callbacks: bool,
+    /// Whether the layout was destroyed with `AllocationCallbacks`, `None` while the layout isn't destroyed.
+    pub destroyed_with_allocation_callbacks: Option<bool>,
+}
+
+/// The `ShaderDevice` that fabricates shader module handles instead of calling Vulkan,
+/// and records every created and destroyed shader module. Descriptor set layouts and pipeline layouts are
+/// fabricated and recorded the same way.
+/// The device created with `failing_after` fails the shader module creation like a real driver can,
+/// and the one created with `failing_layouts_after` fails the layout creation.
+/// # Examples
+///
+/// ```rust
+/// use ash::vk::ShaderStageFlags;
+/// use ash_shader_creator::{MockDevice, ShaderSet, ShaderSource, ShaderStage};
+/// # fn example() -> Result<(), ash_shader_creator::ShaderCreatorError> {
+///
+/// // SPIR-V header and `OpEntryPoint Vertex %1 "main"`.
+/// let vertex_shader = [0x0723_0203, 0x0001_0000, 0, 2, 0, 0x0005_000f, 0, 1, 0x6e69_616d, 0];
+///
+/// let device = MockDevice::new();
+/// let shader_set: ShaderSet<MockDevice> = ShaderStage::from_sources(
+///     &device,
+///     vec![ShaderSource::from_words("sky", None, &vertex_shader)],
+/// )
+/// .build()?;
+/// assert_eq!(shader_set.stages()[0].stage, ShaderStageFlags::VERTEX);
+/// assert_eq!(device.created_modules()[0].code, vertex_shader);
+///
+/// shader_set.destroy(&device);
+/// assert!(device.live_modules().is_empty());
+/// # Ok(())
+/// # }
+/// # example().unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct MockDevice {
+    state: RefCell<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    created_modules: Vec<RecordedShaderModule>,
+    destroyed_modules: Vec<ShaderModule>,
+    failure: Option<(usize, vk::Result)>,
+    descriptor_set_layouts: Vec<RecordedDescriptorSetLayout>,
+    pipeline_layouts: Vec<RecordedPipelineLayout>,
+    layout_failure: Option<(usize, vk::Result)>,
+}
+
+impl MockState {
+    /// Fails if the device already created as many layouts as it was allowed to.
+    fn check_layout_failure(&self) -> Result<(), vk::Result> {
+        match self.layout_failure {
+            Some((created_count, result))
+                if self.descriptor_set_layouts.len() + self.pipeline_layouts.len()
+                    >= created_count =>
+            {
+                Err(result)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MockDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The device that creates `created_count` shader modules and then fails every creation with `result`.
+    pub fn failing_after(created_count: usize, result: vk::Result) -> Self {
+        let device = Self::default();
+        device.state.borrow_mut().failure = Some((created_count, result));
+        device
+    }
+
+    /// The device that creates `created_count` descriptor set layouts and pipeline layouts in total
+    /// and then fails every layout creation with `result`.
+    pub fn failing_layouts_after(created_count: usize, result: vk::Result) -> Self {
+        let device = Self::default();
+        device.state.borrow_mut().layout_failure = Some((created_count, result));
+        device
+    }
+
+    /// Every created shader module in the creation order, including the destroyed ones.
+    pub fn created_modules(&self) -> Vec<RecordedShaderModule> {
+        self.state.borrow().created_modules.clone()
+    }
+
+    /// Every destroyed shader module in the destruction order.
+    pub fn destroyed_modules(&self) -> Vec<ShaderModule> {
+        self.state.borrow().destroyed_modules.clone()
+    }
+
+    /// Created shader modules that aren't destroyed yet.
+    pub fn live_modules(&self) -> Vec<ShaderModule> {
+        let state = self.state.borrow();
+        state
+            .created_modules
+            .iter()
+            .map(|recorded| recorded.module)
+            .filter(|module| !state.destroyed_modules.contains(module))
+            .collect()
+    }
+
+    /// Every created descriptor set layout in the creation order, including the destroyed ones.
+    pub fn created_descriptor_set_layouts(&self) -> Vec<RecordedDescriptorSetLayout> {
+        self.state.borrow().descriptor_set_layouts.clone()
+    }
+
+    /// Every created pipeline layout in the creation order, including the destroyed ones.
+    pub fn created_pipeline_layouts(&self) -> Vec<RecordedPipelineLayout> {
+        self.state.borrow().pipeline_layouts.clone()
+    }
+
+    /// Created descriptor set layouts that aren't destroyed yet.
+    pub fn live_descriptor_set_layouts(&self) -> Vec<DescriptorSetLayout> {
+        self.state
+            .borrow()
+            .descriptor_set_layouts
+            .iter()
+            .filter(|recorded| recorded.destroyed_with_allocation_callbacks.is_none())
+            .map(|recorded| recorded.layout)
+            .collect()
+    }
+
+    /// Created pipeline layouts that aren't destroyed yet.
+    pub fn live_pipeline_layouts(&self) -> Vec<PipelineLayout> {
+        self.state
+            .borrow()
+            .pipeline_layouts
+            .iter()
+            .filter(|recorded| recorded.destroyed_with_allocation_callbacks.is_none())
+            .map(|recorded| recorded.layout)
+            .collect()
+    }
+}
+
+/// Flags of the bindings from `DescriptorSetLayoutBindingFlagsCreateInfo` in the `p_next` chain.
+unsafe fn binding_flags(create_info: &DescriptorSetLayoutCreateInfo) -> &[DescriptorBindingFlags] {
+    let mut next = create_info.p_next as *const BaseInStructure;
+    while let Some(structure) = next.as_ref() {
+        if structure.s_type == StructureType::DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO {
+            let flags_info = &*(next as *const DescriptorSetLayoutBindingFlagsCreateInfo);
+            return raw_slice(flags_info.p_binding_flags, flags_info.binding_count);
+        }
+        next = structure.p_next;
+    }
+    &[]
+}
+
+/// The slice of `count` elements, empty for the null pointer.
+unsafe fn raw_slice<'a, T>(data: *const T, count: u32) -> &'a [T] {
+    if data.is_null() || count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, count as usize)
+    }
+}
+
+impl ShaderDevice for MockDevice {
+    unsafe fn create_shader_module(
+        &self,
+        create_info: &ShaderModuleCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<ShaderModule, vk::Result> {
+        let code = if create_info.code_size == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(create_info.p_code, create_info.code_size / 4).to_vec()
+        };
+
+        let mut state = self.state.borrow_mut();
+        if let Some((created_count, result)) = state.failure {
+            if state.created_modules.len() >= created_count {
+                return Err(result);
+            }
+        }
+
+        // Handles start from 1, `VK_NULL_HANDLE` is never returned.
+        let module = ShaderModule::from_raw(state.created_modules.len() as u64 + 1);
+        state.created_modules.push(RecordedShaderModule {
+            module,
+            flags: create_info.flags,
+            p_next: create_info.p_next,
+            code,
+            with_allocation_callbacks: allocation_callbacks.is_some(),
+            destroyed_with_allocation_callbacks: None,
+        });
+
+        Ok(module)
+    }
+
+    unsafe fn destroy_shader_module(
+        &self,
+        module: ShaderModule,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        let mut state = self.state.borrow_mut();
+        if let Some(recorded) = state
+            .created_modules
+            .iter_mut()
+            .find(|recorded| recorded.module == module)
+        {
+            recorded.destroyed_with_allocation_callbacks = Some(allocation_callbacks.is_some());
+        }
+        state.destroyed_modules.push(module);
+    }
+
+    unsafe fn create_descriptor_set_layout(
+        &self,
+        create_info: &DescriptorSetLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<DescriptorSetLayout, vk::Result> {
+        let binding_flags = binding_flags(create_info);
+        let bindings = raw_slice(create_info.p_bindings, create_info.binding_count)
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| RecordedDescriptorSetLayoutBinding {
+                binding: binding.binding,
+                descriptor_type: binding.descriptor_type,
+                descriptor_count: binding.descriptor_count,
+                stage_flags: binding.stage_flags,
+                immutable_samplers: raw_slice(
+                    binding.p_immutable_samplers,
+                    binding.descriptor_count,
+                )
+                .to_vec(),
+                binding_flags: binding_flags.get(index).copied().unwrap_or_default(),
+            })
+            .collect();
+
+        let mut state = self.state.borrow_mut();
+        state.check_layout_failure()?;
+
+        let layout = DescriptorSetLayout::from_raw(state.descriptor_set_layouts.len() as u64 + 1);
+        state
+            .descriptor_set_layouts
+            .push(RecordedDescriptorSetLayout {
+                layout,
+                flags: create_info.flags,
+                bindings,
+                with_allocation_callbacks: allocation_callbacks.is_some(),
+                destroyed_with_allocation_callbacks: None,
+            });
+
+        Ok(layout)
+    }
+
+    unsafe fn destroy_descriptor_set_layout(
+        &self,
+        layout: DescriptorSetLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        let mut state = self.state.borrow_mut();
+        if let Some(recorded) = state
+            .descriptor_set_layouts
+            .iter_mut()
+            .find(|recorded| recorded.layout == layout)
+        {
+            recorded.destroyed_with_allocation_callbacks = Some(allocation_callbacks.is_some());
+        }
+    }
+
+    unsafe fn create_pipeline_layout(
+        &self,
+        create_info: &PipelineLayoutCreateInfo,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) -> Result<PipelineLayout, vk::Result> {
+        let mut state = self.state.borrow_mut();
+        state.check_layout_failure()?;
+
+        // Handles of the pipeline layouts don't overlap the handles of the descriptor set layouts.
+        let layout = PipelineLayout::from_raw(0x1_0000 + state.pipeline_layouts.len() as u64 + 1);
+        state.pipeline_layouts.push(RecordedPipelineLayout {
+            layout,
+            set_layouts: raw_slice(create_info.p_set_layouts, create_info.set_layout_count)
+                .to_vec(),
+            push_constant_ranges: raw_slice(
+                create_info.p_push_constant_ranges,
+                create_info.push_constant_range_count,
+            )
+            .to_vec(),
+            with_allocation_callbacks: allocation_callbacks.is_some(),
+            destroyed_with_allocation_callbacks: None,
+        });
+
+        Ok(layout)
+    }
+
+    unsafe fn destroy_pipeline_layout(
+        &self,
+        layout: PipelineLayout,
+        allocation_callbacks: Option<&AllocationCallbacks>,
+    ) {
+        let mut state = self.state.borrow_mut();
+        if let Some(recorded) = state
+            .pipeline_layouts
+            .iter_mut()
+            .find(|recorded| recorded.layout == layout)
+        {
+            recorded.destroyed_with_allocation_callbacks = Some(allocation_callbacks.is_some());
+        }
+    }
+}
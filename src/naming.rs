@@ -0,0 +1,343 @@
+//! Naming conventions of the compiled shader files.
+
+use std::path::Path;
+
+use ash::vk::ShaderStageFlags;
+
+/// Stage suffixes of the compiled GLSL shaders, as glslangValidator names them.
+pub const GLSL_STAGE_SUFFIXES: &[(&str, ShaderStageFlags)] = &[
+    ("vert", ShaderStageFlags::VERTEX),
+    ("tesc", ShaderStageFlags::TESSELLATION_CONTROL),
+    ("tese", ShaderStageFlags::TESSELLATION_EVALUATION),
+    ("geom", ShaderStageFlags::GEOMETRY),
+    ("frag", ShaderStageFlags::FRAGMENT),
+    ("comp", ShaderStageFlags::COMPUTE),
+    ("mesh", ShaderStageFlags::MESH_NV),
+    ("task", ShaderStageFlags::TASK_NV),
+    ("rgen", ShaderStageFlags::RAYGEN_KHR),
+    ("rint", ShaderStageFlags::INTERSECTION_KHR),
+    ("rahit", ShaderStageFlags::ANY_HIT_KHR),
+    ("rchit", ShaderStageFlags::CLOSEST_HIT_KHR),
+    ("rmiss", ShaderStageFlags::MISS_KHR),
+    ("rcall", ShaderStageFlags::CALLABLE_KHR),
+];
+
+/// Stage suffixes of the compiled HLSL shaders, named after the HLSL shader model profiles.
+pub const HLSL_STAGE_SUFFIXES: &[(&str, ShaderStageFlags)] = &[
+    ("vs", ShaderStageFlags::VERTEX),
+    ("hs", ShaderStageFlags::TESSELLATION_CONTROL),
+    ("ds", ShaderStageFlags::TESSELLATION_EVALUATION),
+    ("gs", ShaderStageFlags::GEOMETRY),
+    ("ps", ShaderStageFlags::FRAGMENT),
+    ("fs", ShaderStageFlags::FRAGMENT),
+    ("cs", ShaderStageFlags::COMPUTE),
+    ("ms", ShaderStageFlags::MESH_NV),
+    ("as", ShaderStageFlags::TASK_NV),
+];
+
+/// Program name, stage and variant of the compiled shader defined by its file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderName {
+    pub program_name: String,
+    pub stage: ShaderStageFlags,
+    /// Permutation of the program, e.g. `shadow` in `sky.shadow.vert.spv`.
+    /// Every variant of the program is a separate program with its own stages.
+    pub variant: Option<String>,
+}
+
+/// Maps the file name of the compiled shader to its program name, stage and variant.
+/// # Examples
+///
+/// ```rust
+/// use ash::vk::ShaderStageFlags;
+/// use ash_shader_creator::{NamingConvention, ShaderName};
+///
+/// /// `vertex_sky.spv`, `pixel_sky.spv`.
+/// struct PrefixConvention;
+///
+/// impl NamingConvention for PrefixConvention {
+///     fn parse(&self, file_name: &str) -> Option<ShaderName> {
+///         let (prefix, program_name) = file_name.strip_suffix(".spv")?.split_once('_')?;
+///         let stage = match prefix {
+///             "vertex" => ShaderStageFlags::VERTEX,
+///             "pixel" => ShaderStageFlags::FRAGMENT,
+///             _ => return None,
+///         };
+///
+///         Some(ShaderName {
+///             program_name: program_name.to_owned(),
+///             stage,
+///             variant: None,
+///         })
+///     }
+/// }
+///
+/// assert_eq!(PrefixConvention.parse("pixel_sky.spv").unwrap().program_name, "sky");
+/// ```
+pub trait NamingConvention {
+    /// Parses the file name, `None` if it doesn't follow the convention.
+    fn parse(&self, file_name: &str) -> Option<ShaderName>;
+}
+
+/// Naming convention of the files named `<program_name>[.<variant>]<separator><stage_suffix>[.<extension>]`.
+/// Stage suffixes and extensions are matched case-insensitively, but only at the end of the file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuffixConvention {
+    pub stage_suffixes: Vec<(String, ShaderStageFlags)>,
+    pub separators: Vec<String>,
+    /// Extensions after the stage suffix, an empty one means that the stage suffix is the extension itself.
+    pub extensions: Vec<String>,
+}
+
+impl SuffixConvention {
+    pub fn new(
+        stage_suffixes: &[(&str, ShaderStageFlags)],
+        separators: &[&str],
+        extensions: &[&str],
+    ) -> Self {
+        Self {
+            stage_suffixes: stage_suffixes
+                .iter()
+                .map(|(suffix, stage)| ((*suffix).to_owned(), *stage))
+                .collect(),
+            separators: separators
+                .iter()
+                .map(|&separator| separator.to_owned())
+                .collect(),
+            extensions: extensions
+                .iter()
+                .map(|&extension| extension.to_owned())
+                .collect(),
+        }
+    }
+
+    /// glslangValidator outputs: `<program_name>.<stage>.spv`, e.g. `sky.vert.spv`.
+    pub fn glslang() -> Self {
+        Self::new(GLSL_STAGE_SUFFIXES, &["."], &["spv"])
+    }
+
+    /// DXC outputs: `<program_name>.<profile>`, `<program_name>.<profile>.spv` or `<program_name>.<profile>.cso`,
+    /// e.g. `sky.vs`, `sky.ps.spv`. Scanned files without the `.spv` extension are compiled shaders only if they start
+    /// with the SPIR-V magic number, so e.g. `Program.cs` isn't one.
+    pub fn dxc() -> Self {
+        Self::new(HLSL_STAGE_SUFFIXES, &["."], &["", "spv", "cso"])
+    }
+
+    /// Any GLSL or HLSL stage suffix right before `.spv`, separated with `.` or `_`, e.g. `sky.vert.spv` or `sky_vs.spv`.
+    pub fn suffix_before_spv() -> Self {
+        let stage_suffixes: Vec<_> = GLSL_STAGE_SUFFIXES
+            .iter()
+            .chain(HLSL_STAGE_SUFFIXES)
+            .copied()
+            .collect();
+
+        Self::new(&stage_suffixes, &[".", "_"], &["spv"])
+    }
+}
+
+impl NamingConvention for SuffixConvention {
+    fn parse(&self, file_name: &str) -> Option<ShaderName> {
+        for extension in &self.extensions {
+            let stem = if extension.is_empty() {
+                file_name
+            } else {
+                match strip_suffix_ignore_case(file_name, &format!(".{}", extension)) {
+                    Some(stem) => stem,
+                    None => continue,
+                }
+            };
+
+            for (stage_suffix, stage) in &self.stage_suffixes {
+                for separator in &self.separators {
+                    let suffix = format!("{}{}", separator, stage_suffix);
+                    let name = match strip_suffix_ignore_case(stem, &suffix) {
+                        Some(name) if !name.is_empty() => name,
+                        _ => continue,
+                    };
+                    let (program_name, variant) = match name.split_once('.') {
+                        Some((program_name, variant)) => (program_name, Some(variant.to_owned())),
+                        None => (name, None),
+                    };
+
+                    return Some(ShaderName {
+                        program_name: program_name.to_owned(),
+                        stage: *stage,
+                        variant,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Naming conventions used by default: `SuffixConvention::glslang` and `SuffixConvention::dxc`.
+pub fn default_naming_conventions() -> Vec<Box<dyn NamingConvention>> {
+    vec![
+        Box::new(SuffixConvention::glslang()),
+        Box::new(SuffixConvention::dxc()),
+    ]
+}
+
+/// Program name, variant and the stage, if it's known, of the compiled shader.
+pub(crate) struct FileNaming {
+    pub(crate) program_name: String,
+    pub(crate) variant: Option<String>,
+    pub(crate) stage: Option<ShaderStageFlags>,
+}
+
+/// Resolves the naming of the compiled shader from its path relative to the scanned directory with the first
+/// matching convention, program names are prefixed with the subdirectories, e.g. `post/bloom.frag.spv` belongs
+/// to the `post/bloom` program. `.spv` files that follow no convention are named after the file name without
+/// the extension and their stage is defined only from SPIR-V. Returns `None` if the file isn't a compiled shader.
+pub(crate) fn resolve(
+    naming_conventions: &[Box<dyn NamingConvention>],
+    relative_path: &Path,
+) -> Option<FileNaming> {
+    let relative_path = slash_path(relative_path);
+    let (dir_name, file_name) = match relative_path.rsplit_once('/') {
+        Some((dir_name, file_name)) => (Some(dir_name), file_name),
+        None => (None, relative_path.as_str()),
+    };
+
+    let mut naming = match naming_conventions
+        .iter()
+        .find_map(|naming_convention| naming_convention.parse(file_name))
+    {
+        Some(shader_name) => FileNaming {
+            program_name: shader_name.program_name,
+            variant: shader_name.variant,
+            stage: Some(shader_name.stage),
+        },
+        None => FileNaming {
+            program_name: strip_suffix_ignore_case(file_name, ".spv")?.to_owned(),
+            variant: None,
+            stage: None,
+        },
+    };
+
+    if let Some(dir_name) = dir_name {
+        naming.program_name = format!("{}/{}", dir_name, naming.program_name);
+    }
+
+    Some(naming)
+}
+
+/// Joins components of the relative path with `/` on every platform.
+pub(crate) fn slash_path(relative_path: &Path) -> String {
+    relative_path
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Position of the shader stage in the pipeline, used to sort stages of a program.
+pub(crate) fn stage_order(stage: ShaderStageFlags) -> usize {
+    const PIPELINE_ORDER: &[ShaderStageFlags] = &[
+        ShaderStageFlags::TASK_NV,
+        ShaderStageFlags::MESH_NV,
+        ShaderStageFlags::VERTEX,
+        ShaderStageFlags::TESSELLATION_CONTROL,
+        ShaderStageFlags::TESSELLATION_EVALUATION,
+        ShaderStageFlags::GEOMETRY,
+        ShaderStageFlags::FRAGMENT,
+        ShaderStageFlags::COMPUTE,
+        ShaderStageFlags::RAYGEN_KHR,
+        ShaderStageFlags::MISS_KHR,
+        ShaderStageFlags::CLOSEST_HIT_KHR,
+        ShaderStageFlags::ANY_HIT_KHR,
+        ShaderStageFlags::INTERSECTION_KHR,
+        ShaderStageFlags::CALLABLE_KHR,
+    ];
+
+    PIPELINE_ORDER
+        .iter()
+        .position(|pipeline_stage| *pipeline_stage == stage)
+        .unwrap_or(PIPELINE_ORDER.len())
+}
+
+fn strip_suffix_ignore_case<'a>(value: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = value.len().checked_sub(suffix.len())?;
+    if !value.is_char_boundary(split) || !value[split..].eq_ignore_ascii_case(suffix) {
+        return None;
+    }
+
+    Some(&value[..split])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_glslang_and_dxc_suffix_defines_the_stage() {
+        let naming_conventions = default_naming_conventions();
+        let stage = |file_name: &str| {
+            resolve(&naming_conventions, Path::new(file_name)).and_then(|naming| naming.stage)
+        };
+
+        for (suffix, expected) in GLSL_STAGE_SUFFIXES {
+            assert_eq!(stage(&format!("sky.{}.spv", suffix)), Some(*expected));
+            assert_eq!(stage(&format!("sky.{}", suffix)), None);
+        }
+        for (suffix, expected) in HLSL_STAGE_SUFFIXES {
+            assert_eq!(stage(&format!("sky.{}.spv", suffix)), Some(*expected));
+            assert_eq!(stage(&format!("sky.{}", suffix)), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn programs_are_named_after_the_relative_path() {
+        let naming_conventions = default_naming_conventions();
+        let naming = |relative_path: &str| {
+            resolve(&naming_conventions, Path::new(relative_path))
+                .map(|naming| (naming.program_name, naming.variant, naming.stage))
+        };
+
+        assert_eq!(
+            naming("post/bloom.frag.spv"),
+            Some((
+                String::from("post/bloom"),
+                None,
+                Some(ShaderStageFlags::FRAGMENT)
+            ))
+        );
+        assert_eq!(
+            naming("sky.shadow.ps"),
+            Some((
+                String::from("sky"),
+                Some(String::from("shadow")),
+                Some(ShaderStageFlags::FRAGMENT)
+            ))
+        );
+        assert_eq!(naming("cull.spv"), Some((String::from("cull"), None, None)));
+        assert_eq!(naming("notes.txt"), None);
+    }
+
+    #[test]
+    fn stages_are_sorted_in_the_pipeline_order() {
+        let mut stages = vec![
+            ShaderStageFlags::FRAGMENT,
+            ShaderStageFlags::GEOMETRY,
+            ShaderStageFlags::VERTEX,
+            ShaderStageFlags::TESSELLATION_EVALUATION,
+            ShaderStageFlags::TESSELLATION_CONTROL,
+        ];
+        stages.sort_by_key(|stage| stage_order(*stage));
+
+        assert_eq!(
+            stages,
+            [
+                ShaderStageFlags::VERTEX,
+                ShaderStageFlags::TESSELLATION_CONTROL,
+                ShaderStageFlags::TESSELLATION_EVALUATION,
+                ShaderStageFlags::GEOMETRY,
+                ShaderStageFlags::FRAGMENT,
+            ]
+        );
+        assert!(stage_order(ShaderStageFlags::TASK_NV) < stage_order(ShaderStageFlags::MESH_NV));
+        assert!(stage_order(ShaderStageFlags::MESH_NV) < stage_order(ShaderStageFlags::FRAGMENT));
+    }
+}
@@ -0,0 +1,264 @@
+//! Descriptor set layouts and the pipeline layout created from the interface reflected from the stages of a program.
+
+use std::ptr;
+
+use ash::vk::{
+    AllocationCallbacks, DescriptorBindingFlags, DescriptorSetLayout, DescriptorSetLayoutBinding,
+    DescriptorSetLayoutBindingFlagsCreateInfo, DescriptorSetLayoutCreateFlags,
+    DescriptorSetLayoutCreateInfo, DescriptorType, PipelineLayout, PipelineLayoutCreateFlags,
+    PipelineLayoutCreateInfo, PushConstantRange, Sampler, StructureType,
+};
+
+use crate::{reflection, ShaderCreatorError, ShaderDevice, ShaderRecord};
+
+/// Fields of the reflected `DescriptorSetLayoutBinding` to override, `None` fields keep the reflected values.
+#[derive(Debug, Clone, Default)]
+pub struct BindingConfig {
+    /// E.g. `UNIFORM_BUFFER_DYNAMIC` for the reflected `UNIFORM_BUFFER`.
+    pub descriptor_type: Option<DescriptorType>,
+    /// E.g. the length of the runtime array, which is reflected as 0 and must be set.
+    pub descriptor_count: Option<u32>,
+    /// The count of the binding becomes the number of the samplers.
+    pub immutable_samplers: Option<Vec<Sampler>>,
+    /// Chained to the descriptor set layout with `DescriptorSetLayoutBindingFlagsCreateInfo`.
+    pub binding_flags: Option<DescriptorBindingFlags>,
+}
+
+impl BindingConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies `DescriptorType` for the `self.descriptor_type` field.
+    pub fn with_descriptor_type(mut self, descriptor_type: DescriptorType) -> Self {
+        self.descriptor_type = Some(descriptor_type);
+        self
+    }
+
+    /// Specifies the count for the `self.descriptor_count` field.
+    pub fn with_descriptor_count(mut self, descriptor_count: u32) -> Self {
+        self.descriptor_count = Some(descriptor_count);
+        self
+    }
+
+    /// Specifies `Sampler`s for the `self.immutable_samplers` field.
+    pub fn with_immutable_samplers(mut self, immutable_samplers: Vec<Sampler>) -> Self {
+        self.immutable_samplers = Some(immutable_samplers);
+        self
+    }
+
+    /// Specifies `DescriptorBindingFlags` for the `self.binding_flags` field.
+    pub fn with_binding_flags(mut self, binding_flags: DescriptorBindingFlags) -> Self {
+        self.binding_flags = Some(binding_flags);
+        self
+    }
+}
+
+/// Overrides of the layouts `ShaderProgram::create_pipeline_layout_with_config` creates.
+/// If several configs of the same binding set the same field, the last added wins.
+/// Configs of the bindings the program doesn't use are ignored, so one config can be shared by several programs.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineLayoutConfig {
+    /// Configs of the bindings by the set and the binding number.
+    pub bindings: Vec<(u32, u32, BindingConfig)>,
+    /// Flags of the descriptor set layouts by the set number, e.g. `UPDATE_AFTER_BIND_POOL`.
+    pub set_flags: Vec<(u32, DescriptorSetLayoutCreateFlags)>,
+}
+
+impl PipelineLayoutConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the config of the binding of the set to the `self.bindings` field.
+    pub fn with_binding(mut self, set: u32, binding: u32, config: BindingConfig) -> Self {
+        self.bindings.push((set, binding, config));
+        self
+    }
+
+    /// Adds `DescriptorSetLayoutCreateFlags` of the set to the `self.set_flags` field.
+    pub fn with_set_flags(mut self, set: u32, flags: DescriptorSetLayoutCreateFlags) -> Self {
+        self.set_flags.push((set, flags));
+        self
+    }
+
+    /// Applies the configs of the binding, returns its binding flags.
+    fn apply(&self, set: u32, binding: &mut DescriptorSetLayoutBinding) -> DescriptorBindingFlags {
+        let number = binding.binding;
+        let mut binding_flags = DescriptorBindingFlags::empty();
+        for (_, _, config) in self
+            .bindings
+            .iter()
+            .filter(|(config_set, config_binding, _)| {
+                (*config_set, *config_binding) == (set, number)
+            })
+        {
+            if let Some(descriptor_type) = config.descriptor_type {
+                binding.descriptor_type = descriptor_type;
+            }
+            if let Some(descriptor_count) = config.descriptor_count {
+                binding.descriptor_count = descriptor_count;
+            }
+            if let Some(immutable_samplers) = &config.immutable_samplers {
+                binding.descriptor_count = immutable_samplers.len() as u32;
+                binding.p_immutable_samplers = immutable_samplers.as_ptr();
+            }
+            if let Some(flags) = config.binding_flags {
+                binding_flags = flags;
+            }
+        }
+
+        binding_flags
+    }
+
+    fn set_flags(&self, set: u32) -> DescriptorSetLayoutCreateFlags {
+        self.set_flags
+            .iter()
+            .rev()
+            .find(|(config_set, _)| *config_set == set)
+            .map(|(_, flags)| *flags)
+            .unwrap_or_default()
+    }
+}
+
+/// Owns the pipeline layout of the program and the descriptor set layouts it was created with.
+///
+/// Unlike the shader modules, the layouts are used as long as the pipelines created with them, so the layout
+/// doesn't depend on the `ShaderSet` and must be destroyed with `destroy` after the pipelines are destroyed.
+/// The layout has no device to destroy them on drop, so dropping it without `destroy` leaks them,
+/// like dropping the `ShaderSet` built without `ShaderStage::with_destroy_on_drop` leaks its modules.
+#[must_use = "the layouts leak unless they are destroyed with `ProgramLayout::destroy`"]
+#[derive(Debug)]
+pub struct ProgramLayout {
+    pub pipeline_layout: PipelineLayout,
+    /// Layouts indexed by the set number, the sets the program doesn't use in between have empty layouts.
+    pub descriptor_set_layouts: Vec<DescriptorSetLayout>,
+    pub push_constant_ranges: Vec<PushConstantRange>,
+    /// Copy of the `AllocationCallbacks` of the set, so the layout can outlive the set.
+    allocation_callbacks: Option<AllocationCallbacks>,
+}
+
+impl ProgramLayout {
+    /// Consumes the layout and destroys the pipeline layout and then the descriptor set layouts
+    /// with the `AllocationCallbacks` they were created with.
+    pub fn destroy<D: ShaderDevice>(self, device: &D) {
+        let allocation_callbacks = self.allocation_callbacks.as_ref();
+        unsafe { device.destroy_pipeline_layout(self.pipeline_layout, allocation_callbacks) };
+        destroy_descriptor_set_layouts(device, &self.descriptor_set_layouts, allocation_callbacks);
+    }
+}
+
+/// Creates the layouts of the program from the records of its stages,
+/// destroys the created layouts if any creation fails.
+/// Bindings whose count is still 0 after the configs are applied fail before any layout is created.
+pub(crate) fn create_program_layout<D: ShaderDevice>(
+    device: &D,
+    records: &[ShaderRecord],
+    config: &PipelineLayoutConfig,
+    allocation_callbacks: Option<&AllocationCallbacks>,
+) -> Result<ProgramLayout, ShaderCreatorError> {
+    let mut sets = reflection::descriptor_set_bindings(records)?;
+    let push_constant_ranges = reflection::push_constant_ranges(records)?;
+    let set_count = sets.keys().next_back().map_or(0, |set| set + 1);
+
+    let mut configured_sets = Vec::with_capacity(set_count as usize);
+    for set in 0..set_count {
+        let mut bindings = sets.remove(&set).unwrap_or_default();
+        let binding_flags: Vec<_> = bindings
+            .iter_mut()
+            .map(|binding| config.apply(set, binding))
+            .collect();
+        // A binding with the count 0 is reserved and the shader must not access it.
+        if let Some(binding) = bindings
+            .iter()
+            .find(|binding| binding.descriptor_count == 0)
+        {
+            return Err(ShaderCreatorError::UnresolvedDescriptorCount {
+                program_name: records[0].program_name.clone(),
+                variant: records[0].variant.clone(),
+                set,
+                binding: binding.binding,
+            });
+        }
+        configured_sets.push((bindings, binding_flags));
+    }
+
+    let mut descriptor_set_layouts = Vec::with_capacity(set_count as usize);
+    for (set, (bindings, binding_flags)) in (0..).zip(&configured_sets) {
+        let binding_flags_info = DescriptorSetLayoutBindingFlagsCreateInfo {
+            s_type: StructureType::DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO,
+            p_next: ptr::null(),
+            binding_count: binding_flags.len() as u32,
+            p_binding_flags: binding_flags.as_ptr(),
+        };
+        let create_info = DescriptorSetLayoutCreateInfo {
+            s_type: StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+            // Chained only when needed, it requires Vulkan 1.2 or `VK_EXT_descriptor_indexing`.
+            p_next: if binding_flags.iter().any(|flags| !flags.is_empty()) {
+                &binding_flags_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
+            flags: config.set_flags(set),
+            binding_count: bindings.len() as u32,
+            p_bindings: bindings.as_ptr(),
+        };
+
+        match unsafe { device.create_descriptor_set_layout(&create_info, allocation_callbacks) } {
+            Ok(layout) => descriptor_set_layouts.push(layout),
+            Err(result) => {
+                destroy_descriptor_set_layouts(
+                    device,
+                    &descriptor_set_layouts,
+                    allocation_callbacks,
+                );
+                return Err(ShaderCreatorError::CreateDescriptorSetLayout {
+                    program_name: records[0].program_name.clone(),
+                    variant: records[0].variant.clone(),
+                    set,
+                    result,
+                });
+            }
+        }
+    }
+
+    let create_info = PipelineLayoutCreateInfo {
+        s_type: StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+        p_next: ptr::null(),
+        flags: PipelineLayoutCreateFlags::empty(),
+        set_layout_count: descriptor_set_layouts.len() as u32,
+        p_set_layouts: descriptor_set_layouts.as_ptr(),
+        push_constant_range_count: push_constant_ranges.len() as u32,
+        p_push_constant_ranges: push_constant_ranges.as_ptr(),
+    };
+    let pipeline_layout = match unsafe {
+        device.create_pipeline_layout(&create_info, allocation_callbacks)
+    } {
+        Ok(pipeline_layout) => pipeline_layout,
+        Err(result) => {
+            destroy_descriptor_set_layouts(device, &descriptor_set_layouts, allocation_callbacks);
+            return Err(ShaderCreatorError::CreatePipelineLayout {
+                program_name: records[0].program_name.clone(),
+                variant: records[0].variant.clone(),
+                result,
+            });
+        }
+    };
+
+    Ok(ProgramLayout {
+        pipeline_layout,
+        descriptor_set_layouts,
+        push_constant_ranges,
+        allocation_callbacks: allocation_callbacks.copied(),
+    })
+}
+
+fn destroy_descriptor_set_layouts<D: ShaderDevice>(
+    device: &D,
+    layouts: &[DescriptorSetLayout],
+    allocation_callbacks: Option<&AllocationCallbacks>,
+) {
+    for layout in layouts {
+        unsafe { device.destroy_descriptor_set_layout(*layout, allocation_callbacks) };
+    }
+}
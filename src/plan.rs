@@ -0,0 +1,84 @@
+//! Report of the shader stages `ShaderStage::build` would create, made without creating any Vulkan objects.
+
+use std::path::PathBuf;
+
+use ash::vk::ShaderStageFlags;
+
+use crate::ShaderCreatorError;
+
+/// Result of `ShaderStage::plan`: every file the scan found, split into the shaders that would be created,
+/// the ignored files and the invalid ones, and the programs whose layouts would fail to be created.
+#[derive(Debug)]
+pub struct ShaderPlan {
+    /// Shaders `build` would create modules for, sorted like the stages of the `ShaderSet`.
+    pub shaders: Vec<PlannedShader>,
+    /// Files and directories skipped by the scan, sorted by the path.
+    pub ignored_files: Vec<IgnoredFile>,
+    /// Compiled shaders `build` would fail on, in the scan order, then the stages with invalid specialization.
+    /// They aren't in `self.shaders`.
+    pub invalid_shaders: Vec<InvalidShader>,
+    /// Programs whose descriptor bindings or push constant blocks don't match between the stages, sorted like
+    /// `self.shaders`. `build` creates their shaders, but `ShaderProgram::create_pipeline_layout` fails on them.
+    pub invalid_programs: Vec<InvalidProgram>,
+}
+
+impl ShaderPlan {
+    /// Whether `build` would create every planned shader and the layouts of every program can be reflected,
+    /// i.e. there are no invalid shaders and programs. Errors of Vulkan itself can't be planned.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_shaders.is_empty() && self.invalid_programs.is_empty()
+    }
+}
+
+/// The stage of the compiled shader `build` would create the shader module for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedShader {
+    /// Path of the compiled shader file, or the name of the `ShaderSource`.
+    pub path: PathBuf,
+    pub program_name: String,
+    pub variant: Option<String>,
+    pub stage: ShaderStageFlags,
+    pub entry_point: String,
+}
+
+/// The file or directory skipped by the scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoredFile {
+    pub path: PathBuf,
+    pub reason: IgnoreReason,
+}
+
+/// Why the file or directory was skipped by the scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreReason {
+    /// The name starts with `.` and hidden files are skipped.
+    Hidden,
+    /// The path matches one of the exclude patterns.
+    Excluded,
+    /// The path matches none of the include patterns.
+    NotIncluded,
+    /// The subdirectory isn't scanned because the scan isn't recursive.
+    NotRecursive,
+    /// The subdirectory is a symbolic link to the directory that contains it, so it's already being scanned.
+    Cycle,
+    /// The file follows none of the naming conventions and isn't a `.spv` file,
+    /// or it isn't a `.spv` file and doesn't start with the SPIR-V magic number, e.g. `Program.cs`.
+    NotShader,
+}
+
+/// The compiled shader `build` would fail on.
+#[derive(Debug)]
+pub struct InvalidShader {
+    /// Path of the compiled shader file, or the name of the `ShaderSource`.
+    pub path: PathBuf,
+    pub error: ShaderCreatorError,
+}
+
+/// The program whose layouts `ShaderProgram::create_pipeline_layout` would fail to reflect.
+#[derive(Debug)]
+pub struct InvalidProgram {
+    pub program_name: String,
+    pub variant: Option<String>,
+    /// The error of the first mismatch, its path is the stage that doesn't match the previous ones.
+    pub error: ShaderCreatorError,
+}
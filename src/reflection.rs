@@ -0,0 +1,1202 @@
+//! Reflection of the interface of the loaded SPIR-V modules.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ptr,
+};
+
+use ash::vk::{
+    DescriptorSetLayoutBinding, DescriptorType, Format, PushConstantRange, ShaderStageFlags,
+};
+
+use crate::{
+    spirv::{self, EntryPoint, Instruction},
+    ShaderCreatorError, ShaderRecord, SpecializationValue,
+};
+
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_TYPE_FORWARD_POINTER: u32 = 39;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_FUNCTION_CALL: u32 = 57;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_TYPE_ACCELERATION_STRUCTURE_KHR: u32 = 5341;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ROW_MAJOR: u32 = 4;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/// `Sampled` operand of `OpTypeImage` for the images used without a sampler.
+const IMAGE_STORAGE: u32 = 2;
+
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+
+/// Nesting depth of the types, deeper types make the module malformed, so their reflection can't overflow the stack.
+const MAX_TYPE_DEPTH: u32 = 256;
+/// Count of the types nested in a type, counted once for every place they are used in, larger types make
+/// the module malformed, so the types sharing their members can't make the reflection exponentially long.
+const MAX_TYPE_NODES: u32 = 1 << 16;
+
+/// Interface of the SPIR-V module.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderReflection {
+    /// Specialization constants in the order they are declared in the module.
+    pub specialization_constants: Vec<SpecializationConstantInfo>,
+    /// Resources decorated with `DescriptorSet` and `Binding`, sorted by the set and the binding.
+    /// For the module with several entry points, only the resources the functions of the entry points of the stage
+    /// reference, all of them if the functions can't be found.
+    pub descriptor_bindings: Vec<DescriptorBindingInfo>,
+    /// Blocks in the `PushConstant` storage class, one for every such `OpVariable` of the module.
+    /// For the module with several entry points, only the blocks the functions of the entry points of the stage
+    /// reference, all of them if the functions can't be found.
+    pub push_constant_blocks: Vec<PushConstantBlockInfo>,
+    /// Inputs of the vertex entry points decorated with `Location`, sorted by the location, built-ins excluded.
+    pub vertex_inputs: Vec<VertexInputInfo>,
+}
+
+impl ShaderReflection {
+    /// The specialization constant with the `constant_id`, `None` if the module doesn't declare it.
+    pub fn specialization_constant(&self, constant_id: u32) -> Option<&SpecializationConstantInfo> {
+        self.specialization_constants
+            .iter()
+            .find(|constant| constant.constant_id == constant_id)
+    }
+}
+
+/// The specialization constant declared with `OpSpecConstant*` and decorated with `SpecId`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecializationConstantInfo {
+    /// `SpecId` of the constant, `constant_id` of the `SpecializationMapEntry`.
+    pub constant_id: u32,
+    /// Name of the constant from `OpName`, `None` if the module is stripped.
+    pub name: Option<String>,
+    pub constant_type: SpecializationConstantType,
+    /// `None` for the `Other` types.
+    pub default_value: Option<SpecializationValue>,
+}
+
+/// The resource variable decorated with `DescriptorSet` and `Binding`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorBindingInfo {
+    pub set: u32,
+    pub binding: u32,
+    /// Name of the variable from `OpName`, or of its block if the variable is unnamed,
+    /// `None` if the module is stripped.
+    pub name: Option<String>,
+    pub descriptor_type: DescriptorType,
+    /// Product of the lengths of the arrays, an array sized by a specialization constant uses its default value.
+    /// 0 for the runtime arrays and the arrays whose length can't be resolved, e.g. computed with `OpSpecConstantOp`,
+    /// the count of such bindings has to be set by the application.
+    pub descriptor_count: u32,
+}
+
+/// The block in the `PushConstant` storage class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConstantBlockInfo {
+    /// Name of the variable from `OpName`, or of its block if the variable is unnamed,
+    /// `None` if the module is stripped.
+    pub name: Option<String>,
+    /// `Offset` of the first member.
+    pub offset: u32,
+    /// Bytes from the first member to the end of the last one, rounded up to a multiple of 4.
+    pub size: u32,
+    /// Members sorted by the offset.
+    pub members: Vec<PushConstantMemberInfo>,
+}
+
+/// The member of the push constant block, nested structs are reflected as a single member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConstantMemberInfo {
+    /// Name of the member from `OpMemberName`, `None` if the module is stripped.
+    pub name: Option<String>,
+    pub offset: u32,
+    pub size: u32,
+    /// GLSL spelling of the type, e.g. `vec4`, `mat4x3` or `float[3]`, structs are spelled with the types
+    /// of their members, e.g. `struct { vec4, uint }`.
+    pub type_name: String,
+}
+
+/// The input variable of the vertex stage, matrices and arrays are split into several attributes,
+/// one per column and element, at the consecutive locations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexInputInfo {
+    /// `Location` of the variable, the location of the first attribute.
+    pub location: u32,
+    /// Name of the variable from `OpName`, `None` if the module is stripped.
+    pub name: Option<String>,
+    /// Format of every attribute, e.g. `R32G32B32_SFLOAT` for `vec3` and for the columns of `mat3`.
+    pub format: Format,
+    /// Size of every attribute in bytes.
+    pub size: u32,
+    /// Count of the attributes, the columns of the matrix times the elements of the array, 1 for other types.
+    pub attribute_count: u32,
+    /// Locations every attribute consumes, 2 for the 64-bit vectors of 3 and 4 components, 1 for other types.
+    pub locations_per_attribute: u32,
+}
+
+/// Type of the specialization constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecializationConstantType {
+    Bool,
+    I32,
+    U32,
+    F32,
+    F64,
+    /// The scalar type `SpecializationValue` has no variant for, e.g. 64-bit integers.
+    Other {
+        size: usize,
+    },
+}
+
+impl SpecializationConstantType {
+    /// Size of the constant in the specialization data, `VkBool32` for the booleans.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Bool | Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+            Self::Other { size } => size,
+        }
+    }
+
+    /// Whether the value can specialize the constant of this type,
+    /// integers of either signedness are accepted for both signed and unsigned constants.
+    pub fn accepts(self, value: SpecializationValue) -> bool {
+        matches!(
+            (self, value),
+            (Self::Bool, SpecializationValue::Bool(_))
+                | (
+                    Self::I32 | Self::U32,
+                    SpecializationValue::I32(_) | SpecializationValue::U32(_)
+                )
+                | (Self::F32, SpecializationValue::F32(_))
+                | (Self::F64, SpecializationValue::F64(_))
+        )
+    }
+}
+
+/// Type declared with `OpType*`, the reflected types refer to others by their ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type<'a> {
+    Bool,
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Vector {
+        component: u32,
+        count: u32,
+    },
+    /// `column` is the vector type of the columns.
+    Matrix {
+        column: u32,
+        count: u32,
+    },
+    Image {
+        dim: u32,
+        sampled: u32,
+    },
+    Sampler,
+    SampledImage,
+    /// `length` is `None` for the runtime arrays.
+    Array {
+        element: u32,
+        length: Option<u32>,
+    },
+    Struct {
+        members: &'a [u32],
+    },
+    Pointer {
+        pointee: u32,
+    },
+    AccelerationStructure,
+}
+
+/// A size or a count of the module overflows `u32`, so the module is malformed.
+struct Overflow;
+
+/// Instructions of the SPIR-V module indexed by the result id.
+struct Module<'a> {
+    instructions: Vec<Instruction<'a>>,
+    names: HashMap<u32, String>,
+    decorations: HashMap<u32, Vec<&'a [u32]>>,
+    /// Names of the struct members by the struct id and the member index.
+    member_names: HashMap<(u32, u32), String>,
+    member_decorations: HashMap<(u32, u32), Vec<&'a [u32]>>,
+    types: HashMap<u32, Type<'a>>,
+    /// Depth and count of the nested types of every type, the type itself included.
+    /// Pointers, declared ahead with `OpTypeForwardPointer` or not, nest no types.
+    type_nesting: HashMap<u32, (u32, u32)>,
+    /// Values of the 32-bit integer constants, default values of the specialization constants included.
+    constants: HashMap<u32, u32>,
+}
+
+impl<'a> Module<'a> {
+    /// `None` if the module is malformed, e.g. its types are cyclic or refer to the types declared after them.
+    fn parse(words: &'a [u32]) -> Option<Self> {
+        let instructions = spirv::instructions(words)?;
+        let mut module = Self {
+            instructions: Vec::new(),
+            names: HashMap::new(),
+            decorations: HashMap::new(),
+            member_names: HashMap::new(),
+            member_decorations: HashMap::new(),
+            types: HashMap::new(),
+            type_nesting: HashMap::new(),
+            constants: HashMap::new(),
+        };
+
+        for instruction in &instructions {
+            let operands = instruction.operands;
+            match instruction.opcode {
+                OP_NAME if operands.len() >= 2 => {
+                    if let Some(name) = spirv::literal_string(&operands[1..]) {
+                        module.names.insert(operands[0], name);
+                    }
+                }
+                OP_MEMBER_NAME if operands.len() >= 3 => {
+                    if let Some(name) = spirv::literal_string(&operands[2..]) {
+                        module.member_names.insert((operands[0], operands[1]), name);
+                    }
+                }
+                // Decoration and its literals.
+                OP_DECORATE if operands.len() >= 2 => module
+                    .decorations
+                    .entry(operands[0])
+                    .or_default()
+                    .push(&operands[1..]),
+                OP_MEMBER_DECORATE if operands.len() >= 3 => module
+                    .member_decorations
+                    .entry((operands[0], operands[1]))
+                    .or_default()
+                    .push(&operands[2..]),
+                OP_TYPE_BOOL if !operands.is_empty() => {
+                    module.declare_type(operands[0], Type::Bool)?
+                }
+                OP_TYPE_INT if operands.len() >= 3 => {
+                    let int_type = Type::Int {
+                        width: operands[1],
+                        signed: operands[2] != 0,
+                    };
+                    module.declare_type(operands[0], int_type)?
+                }
+                OP_TYPE_FLOAT if operands.len() >= 2 => {
+                    let float_type = Type::Float { width: operands[1] };
+                    module.declare_type(operands[0], float_type)?
+                }
+                OP_TYPE_VECTOR if operands.len() >= 3 => {
+                    let vector_type = Type::Vector {
+                        component: operands[1],
+                        count: operands[2],
+                    };
+                    module.declare_type(operands[0], vector_type)?
+                }
+                OP_TYPE_MATRIX if operands.len() >= 3 => {
+                    let matrix_type = Type::Matrix {
+                        column: operands[1],
+                        count: operands[2],
+                    };
+                    module.declare_type(operands[0], matrix_type)?
+                }
+                OP_TYPE_IMAGE if operands.len() >= 7 => {
+                    let image_type = Type::Image {
+                        dim: operands[2],
+                        sampled: operands[6],
+                    };
+                    module.declare_type(operands[0], image_type)?
+                }
+                OP_TYPE_SAMPLER if !operands.is_empty() => {
+                    module.declare_type(operands[0], Type::Sampler)?
+                }
+                OP_TYPE_SAMPLED_IMAGE if !operands.is_empty() => {
+                    module.declare_type(operands[0], Type::SampledImage)?
+                }
+                OP_TYPE_ARRAY if operands.len() >= 3 => {
+                    // The length is the id of the constant, resolved once all constants are known.
+                    let array_type = Type::Array {
+                        element: operands[1],
+                        length: Some(operands[2]),
+                    };
+                    module.declare_type(operands[0], array_type)?
+                }
+                OP_TYPE_RUNTIME_ARRAY if operands.len() >= 2 => {
+                    let array_type = Type::Array {
+                        element: operands[1],
+                        length: None,
+                    };
+                    module.declare_type(operands[0], array_type)?
+                }
+                OP_TYPE_STRUCT if !operands.is_empty() => {
+                    let struct_type = Type::Struct {
+                        members: &operands[1..],
+                    };
+                    module.declare_type(operands[0], struct_type)?
+                }
+                OP_TYPE_POINTER if operands.len() >= 3 => {
+                    let pointer_type = Type::Pointer {
+                        pointee: operands[2],
+                    };
+                    module.declare_type(operands[0], pointer_type)?
+                }
+                OP_TYPE_FORWARD_POINTER if !operands.is_empty() => {
+                    module.type_nesting.entry(operands[0]).or_insert((1, 1));
+                }
+                OP_TYPE_ACCELERATION_STRUCTURE_KHR if !operands.is_empty() => {
+                    module.declare_type(operands[0], Type::AccelerationStructure)?
+                }
+                OP_CONSTANT | OP_SPEC_CONSTANT if operands.len() >= 3 => {
+                    module.constants.insert(operands[1], operands[2]);
+                }
+                _ => (),
+            }
+        }
+
+        module.instructions = instructions;
+        Some(module)
+    }
+
+    /// Declares the type, `None` if its id is already declared, it nests types that aren't declared before it,
+    /// or it's nested too deep. So the types can't be cyclic and only pointers can refer to the types after them.
+    fn declare_type(&mut self, id: u32, declared_type: Type<'a>) -> Option<()> {
+        // Only the pointers can be declared ahead with `OpTypeForwardPointer`.
+        let forward_pointer = matches!(declared_type, Type::Pointer { .. });
+        if self.types.contains_key(&id) || (self.type_nesting.contains_key(&id) && !forward_pointer)
+        {
+            return None;
+        }
+
+        let nested_types = match &declared_type {
+            Type::Vector { component, .. } => std::slice::from_ref(component),
+            Type::Matrix { column, .. } => std::slice::from_ref(column),
+            Type::Array { element, .. } => std::slice::from_ref(element),
+            Type::Struct { members } => members,
+            _ => &[],
+        };
+        let (mut depth, mut nodes) = (0, 1u32);
+        for nested_type in nested_types {
+            let (nested_depth, nested_nodes) = *self.type_nesting.get(nested_type)?;
+            depth = depth.max(nested_depth);
+            nodes = nodes.saturating_add(nested_nodes);
+        }
+        if depth >= MAX_TYPE_DEPTH || nodes > MAX_TYPE_NODES {
+            return None;
+        }
+
+        self.types.insert(id, declared_type);
+        self.type_nesting.insert(id, (depth + 1, nodes));
+        Some(())
+    }
+
+    /// Length of the array with the length of the constant `length_id`, `None` if the constant isn't known.
+    fn array_length(&self, length_id: u32) -> Option<u32> {
+        self.constants.get(&length_id).copied()
+    }
+
+    /// Literals of the decoration of the id, `None` if the id isn't decorated with it.
+    fn decoration(&self, id: u32, decoration: u32) -> Option<&'a [u32]> {
+        self.decorations
+            .get(&id)?
+            .iter()
+            .find(|decorate| decorate[0] == decoration)
+            .map(|decorate| &decorate[1..])
+    }
+
+    /// Literals of the decoration of the struct member, `None` if the member isn't decorated with it.
+    fn member_decoration(&self, struct_id: u32, member: u32, decoration: u32) -> Option<&'a [u32]> {
+        self.member_decorations
+            .get(&(struct_id, member))?
+            .iter()
+            .find(|decorate| decorate[0] == decoration)
+            .map(|decorate| &decorate[1..])
+    }
+
+    /// Size of the value of the type in the explicitly laid out block, `None` for the types without a size.
+    /// `matrix_layout` is the `MatrixStride` and whether the member is `RowMajor`, for the matrices and their arrays.
+    fn size_of(
+        &self,
+        type_id: u32,
+        matrix_layout: Option<(u32, bool)>,
+    ) -> Option<Result<u32, Overflow>> {
+        let size = match *self.types.get(&type_id)? {
+            // Booleans are 32-bit when they are stored at all.
+            Type::Bool => Ok(4),
+            Type::Int { width, .. } | Type::Float { width } => Ok(width / 8),
+            Type::Vector { component, count } => self
+                .size_of(component, None)?
+                .and_then(|size| checked_mul(size, count)),
+            Type::Matrix { column, count } => match (matrix_layout, self.types.get(&column)) {
+                (Some((stride, false)), _) => checked_mul(stride, count),
+                (Some((stride, true)), Some(Type::Vector { count: rows, .. })) => {
+                    checked_mul(stride, *rows)
+                }
+                _ => self
+                    .size_of(column, None)?
+                    .and_then(|size| checked_mul(size, count)),
+            },
+            Type::Array { element, length } => {
+                let length = match length {
+                    Some(length_id) => self.array_length(length_id)?,
+                    None => return Some(Ok(0)),
+                };
+                let stride = match self.decoration(type_id, DECORATION_ARRAY_STRIDE) {
+                    Some([stride, ..]) => Ok(*stride),
+                    _ => self.size_of(element, matrix_layout)?,
+                };
+                stride.and_then(|stride| checked_mul(stride, length))
+            }
+            Type::Struct { members } => (0..members.len() as u32)
+                .map(|member| self.member_layout(type_id, member))
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .try_fold(0, |size: u32, layout| {
+                    let (offset, member_size) = layout?;
+                    Ok(size.max(offset.checked_add(member_size).ok_or(Overflow)?))
+                }),
+            // Only the physical storage buffer pointers can be stored in blocks.
+            Type::Pointer { .. } => Ok(8),
+            _ => return None,
+        };
+
+        Some(size)
+    }
+
+    /// GLSL spelling of the type, `None` for the types that can't be stored in blocks.
+    fn type_name(&self, type_id: u32) -> Option<String> {
+        let type_name = match *self.types.get(&type_id)? {
+            Type::Bool => String::from("bool"),
+            Type::Int { width: 32, signed } => String::from(if signed { "int" } else { "uint" }),
+            Type::Int { width, signed } => {
+                format!("{}int{}_t", if signed { "" } else { "u" }, width)
+            }
+            Type::Float { width: 32 } => String::from("float"),
+            Type::Float { width: 64 } => String::from("double"),
+            Type::Float { width } => format!("float{}_t", width),
+            Type::Vector { component, count } => {
+                format!("{}vec{}", self.vector_prefix(component)?, count)
+            }
+            Type::Matrix { column, count } => match *self.types.get(&column)? {
+                Type::Vector {
+                    component,
+                    count: rows,
+                } if rows == count => format!("{}mat{}", self.vector_prefix(component)?, count),
+                Type::Vector {
+                    component,
+                    count: rows,
+                } => format!("{}mat{}x{}", self.vector_prefix(component)?, count, rows),
+                _ => return None,
+            },
+            Type::Array { element, length } => {
+                let element = self.type_name(element)?;
+                match length {
+                    Some(length_id) => format!("{}[{}]", element, self.array_length(length_id)?),
+                    None => format!("{}[]", element),
+                }
+            }
+            Type::Struct { members } => {
+                let members = members
+                    .iter()
+                    .map(|member| self.type_name(*member))
+                    .collect::<Option<Vec<_>>>()?;
+                format!("struct {{ {} }}", members.join(", "))
+            }
+            Type::Pointer { .. } => String::from("pointer"),
+            _ => return None,
+        };
+
+        Some(type_name)
+    }
+
+    /// Prefix of the GLSL vector and matrix types with the component type, e.g. `i` of `ivec2`.
+    fn vector_prefix(&self, component: u32) -> Option<String> {
+        let prefix = match *self.types.get(&component)? {
+            Type::Bool => String::from("b"),
+            Type::Int { width: 32, signed } => String::from(if signed { "i" } else { "u" }),
+            Type::Int { width, signed } => format!("{}{}", if signed { "i" } else { "u" }, width),
+            Type::Float { width: 32 } => String::new(),
+            Type::Float { width: 64 } => String::from("d"),
+            Type::Float { width } => format!("f{}", width),
+            _ => return None,
+        };
+
+        Some(prefix)
+    }
+
+    /// `Offset` and size of the struct member.
+    fn member_layout(&self, struct_id: u32, member: u32) -> Option<Result<(u32, u32), Overflow>> {
+        let member_type = match self.types.get(&struct_id)? {
+            Type::Struct { members } => *members.get(member as usize)?,
+            _ => return None,
+        };
+        let offset = *self
+            .member_decoration(struct_id, member, DECORATION_OFFSET)?
+            .first()?;
+        let matrix_layout = self
+            .member_decoration(struct_id, member, DECORATION_MATRIX_STRIDE)
+            .and_then(|stride| stride.first())
+            .map(|stride| {
+                let row_major = self
+                    .member_decoration(struct_id, member, DECORATION_ROW_MAJOR)
+                    .is_some();
+                (*stride, row_major)
+            });
+
+        let size = self.size_of(member_type, matrix_layout)?;
+        Some(size.map(|size| (offset, size)))
+    }
+
+    /// `referenced_ids` limit the blocks to the ones of one stage of the module.
+    fn push_constant_blocks(
+        &self,
+        referenced_ids: Option<&HashSet<u32>>,
+    ) -> Result<Vec<PushConstantBlockInfo>, Overflow> {
+        self.variables(referenced_ids)
+            .filter_map(|instruction| self.push_constant_block(instruction))
+            .collect()
+    }
+
+    /// Reflects `OpVariable`, `None` if the variable isn't the block in the `PushConstant` storage class.
+    fn push_constant_block(
+        &self,
+        instruction: &Instruction,
+    ) -> Option<Result<PushConstantBlockInfo, Overflow>> {
+        let (result_type, id) = match instruction.operands {
+            [result_type, id, STORAGE_CLASS_PUSH_CONSTANT, ..] => (*result_type, *id),
+            _ => return None,
+        };
+        let struct_id = match self.types.get(&result_type)? {
+            Type::Pointer { pointee } => *pointee,
+            _ => return None,
+        };
+        let member_types = match self.types.get(&struct_id)? {
+            Type::Struct { members } => *members,
+            _ => return None,
+        };
+
+        let members = (0..member_types.len() as u32)
+            .zip(member_types)
+            .map(|(member, member_type)| {
+                let layout = self.member_layout(struct_id, member)?;
+                let type_name = self.type_name(*member_type)?;
+                Some(layout.map(|(offset, size)| PushConstantMemberInfo {
+                    name: self.member_names.get(&(struct_id, member)).cloned(),
+                    offset,
+                    size,
+                    type_name,
+                }))
+            })
+            .collect::<Option<Result<Vec<_>, _>>>()?;
+
+        Some(members.and_then(|mut members| {
+            members.sort_by_key(|member| member.offset);
+            let offset = members
+                .iter()
+                .map(|member| member.offset)
+                .min()
+                .unwrap_or(0);
+            let end = members
+                .iter()
+                .map(|member| member.offset.checked_add(member.size).ok_or(Overflow))
+                .try_fold(0, |end: u32, member_end| Ok(end.max(member_end?)))?;
+            let size = (end - offset).checked_add(3).ok_or(Overflow)? / 4 * 4;
+
+            Ok(PushConstantBlockInfo {
+                name: self.variable_name(id, struct_id),
+                offset,
+                size,
+                members,
+            })
+        }))
+    }
+
+    /// Name of the variable, or of its type if the variable is unnamed.
+    fn variable_name(&self, id: u32, type_id: u32) -> Option<String> {
+        self.names
+            .get(&id)
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.names.get(&type_id))
+            .cloned()
+    }
+
+    fn vertex_inputs(&self, entry_points: &[EntryPoint]) -> Result<Vec<VertexInputInfo>, Overflow> {
+        let mut vertex_inputs = entry_points
+            .iter()
+            .filter(|entry_point| entry_point.execution_model == EXECUTION_MODEL_VERTEX)
+            .flat_map(|entry_point| entry_point.interface.iter())
+            .filter_map(|id| self.vertex_input(*id))
+            .collect::<Result<Vec<_>, _>>()?;
+        vertex_inputs.sort_by_key(|vertex_input| vertex_input.location);
+        // Several vertex entry points can use the same variable.
+        vertex_inputs.dedup();
+
+        Ok(vertex_inputs)
+    }
+
+    /// Reflects the interface variable, `None` if it isn't the `Input` variable decorated with `Location`
+    /// or its type has no vertex format.
+    fn vertex_input(&self, id: u32) -> Option<Result<VertexInputInfo, Overflow>> {
+        let result_type = self.instructions.iter().find_map(|instruction| {
+            match (instruction.opcode, instruction.operands) {
+                (OP_VARIABLE, [result_type, variable_id, STORAGE_CLASS_INPUT, ..])
+                    if *variable_id == id =>
+                {
+                    Some(*result_type)
+                }
+                _ => None,
+            }
+        })?;
+        if self.decoration(id, DECORATION_BUILT_IN).is_some() {
+            return None;
+        }
+        let location = *self.decoration(id, DECORATION_LOCATION)?.first()?;
+
+        let mut type_id = match self.types.get(&result_type)? {
+            Type::Pointer { pointee } => *pointee,
+            _ => return None,
+        };
+        // Array types nest only the types declared before them, so the loop ends.
+        let mut attribute_count = Ok(1);
+        while let Some(Type::Array {
+            element,
+            length: Some(length_id),
+        }) = self.types.get(&type_id)
+        {
+            let length = self.array_length(*length_id)?;
+            attribute_count = attribute_count.and_then(|count| checked_mul(count, length));
+            type_id = *element;
+        }
+        if let Some(Type::Matrix { column, count }) = self.types.get(&type_id) {
+            attribute_count =
+                attribute_count.and_then(|attribute_count| checked_mul(attribute_count, *count));
+            type_id = *column;
+        }
+        let (component, component_count) = match *self.types.get(&type_id)? {
+            Type::Vector { component, count } => (*self.types.get(&component)?, count),
+            scalar => (scalar, 1),
+        };
+
+        let (format, width) = vertex_format(component, component_count)?;
+        let size = width / 8 * component_count;
+        let locations_per_attribute = if size > 16 { 2 } else { 1 };
+        // The locations and the vertex stride of all attributes must fit in `u32`.
+        let attribute_count = attribute_count.and_then(|attribute_count| {
+            checked_mul(attribute_count, locations_per_attribute)?
+                .checked_add(location)
+                .ok_or(Overflow)?;
+            checked_mul(attribute_count, size)?;
+            Ok(attribute_count)
+        });
+
+        Some(attribute_count.map(|attribute_count| VertexInputInfo {
+            location,
+            name: self.names.get(&id).cloned(),
+            format,
+            size,
+            attribute_count,
+            locations_per_attribute,
+        }))
+    }
+
+    /// Ids referenced by the functions the entry points of the stage call, directly or through other functions.
+    /// `None` if the function of an entry point isn't found.
+    fn referenced_ids(
+        &self,
+        entry_points: &[EntryPoint],
+        stage: ShaderStageFlags,
+    ) -> Option<HashSet<u32>> {
+        // Instructions of the function bodies by the function id.
+        let mut functions = HashMap::new();
+        let mut function = None;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            match (instruction.opcode, instruction.operands) {
+                (OP_FUNCTION, [_, id, ..]) => function = Some((*id, index + 1)),
+                (OP_FUNCTION_END, _) => {
+                    if let Some((id, start)) = function.take() {
+                        functions.insert(id, &self.instructions[start..index]);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut pending: Vec<_> = entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage() == Some(stage))
+            .map(|entry_point| entry_point.function)
+            .collect();
+        let mut visited = HashSet::new();
+        let mut referenced_ids = HashSet::new();
+        while let Some(function_id) = pending.pop() {
+            if !visited.insert(function_id) {
+                continue;
+            }
+            for instruction in *functions.get(&function_id)? {
+                if let (OP_FUNCTION_CALL, [_, _, callee, ..]) =
+                    (instruction.opcode, instruction.operands)
+                {
+                    pending.push(*callee);
+                }
+                // Literals are taken for ids too, which can only keep an unused resource.
+                referenced_ids.extend(instruction.operands);
+            }
+        }
+
+        Some(referenced_ids)
+    }
+
+    /// `OpVariable` instructions of the module, only the ones in `referenced_ids` if they are known.
+    fn variables<'m>(
+        &'m self,
+        referenced_ids: Option<&'m HashSet<u32>>,
+    ) -> impl Iterator<Item = &'m Instruction<'a>> {
+        self.instructions
+            .iter()
+            .filter(|instruction| instruction.opcode == OP_VARIABLE)
+            .filter(
+                move |instruction| match (referenced_ids, instruction.operands) {
+                    (Some(referenced_ids), [_, id, ..]) => referenced_ids.contains(id),
+                    _ => true,
+                },
+            )
+    }
+
+    /// `referenced_ids` limit the bindings to the resources of one stage of the module.
+    fn descriptor_bindings(
+        &self,
+        referenced_ids: Option<&HashSet<u32>>,
+    ) -> Result<Vec<DescriptorBindingInfo>, Overflow> {
+        let mut descriptor_bindings = self
+            .variables(referenced_ids)
+            .filter_map(|instruction| self.descriptor_binding(instruction))
+            .collect::<Result<Vec<_>, _>>()?;
+        descriptor_bindings
+            .sort_by_key(|descriptor_binding| (descriptor_binding.set, descriptor_binding.binding));
+
+        Ok(descriptor_bindings)
+    }
+
+    /// Reflects `OpVariable`, `None` if the variable isn't the resource decorated with `DescriptorSet` and `Binding`.
+    fn descriptor_binding(
+        &self,
+        instruction: &Instruction,
+    ) -> Option<Result<DescriptorBindingInfo, Overflow>> {
+        let (result_type, id, storage_class) = match instruction.operands {
+            [result_type, id, storage_class, ..] => (*result_type, *id, *storage_class),
+            _ => return None,
+        };
+        let set = *self.decoration(id, DECORATION_DESCRIPTOR_SET)?.first()?;
+        let binding = *self.decoration(id, DECORATION_BINDING)?.first()?;
+
+        let mut type_id = match self.types.get(&result_type)? {
+            Type::Pointer { pointee } => *pointee,
+            _ => return None,
+        };
+        // Arrays of arrays are flattened into one binding, any length that isn't known makes the count unresolved.
+        // Array types nest only the types declared before them, so the loop ends.
+        let mut descriptor_count = Ok(1);
+        while let Some(Type::Array { element, length }) = self.types.get(&type_id) {
+            let length = length
+                .and_then(|length_id| self.array_length(length_id))
+                .unwrap_or(0);
+            descriptor_count = descriptor_count.and_then(|count| checked_mul(count, length));
+            type_id = *element;
+        }
+
+        let descriptor_type = match (storage_class, *self.types.get(&type_id)?) {
+            (STORAGE_CLASS_UNIFORM_CONSTANT, Type::Sampler) => DescriptorType::SAMPLER,
+            (STORAGE_CLASS_UNIFORM_CONSTANT, Type::SampledImage) => {
+                DescriptorType::COMBINED_IMAGE_SAMPLER
+            }
+            (STORAGE_CLASS_UNIFORM_CONSTANT, Type::Image { dim, sampled }) => {
+                match (dim, sampled) {
+                    (DIM_BUFFER, IMAGE_STORAGE) => DescriptorType::STORAGE_TEXEL_BUFFER,
+                    (DIM_BUFFER, _) => DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    (DIM_SUBPASS_DATA, _) => DescriptorType::INPUT_ATTACHMENT,
+                    (_, IMAGE_STORAGE) => DescriptorType::STORAGE_IMAGE,
+                    _ => DescriptorType::SAMPLED_IMAGE,
+                }
+            }
+            (STORAGE_CLASS_UNIFORM_CONSTANT, Type::AccelerationStructure) => {
+                DescriptorType::ACCELERATION_STRUCTURE_KHR
+            }
+            // Before SPIR-V 1.3 storage buffers are `Uniform` blocks decorated with `BufferBlock`.
+            (STORAGE_CLASS_UNIFORM, Type::Struct { .. })
+                if self.decoration(type_id, DECORATION_BUFFER_BLOCK).is_some() =>
+            {
+                DescriptorType::STORAGE_BUFFER
+            }
+            (STORAGE_CLASS_UNIFORM, Type::Struct { .. }) => DescriptorType::UNIFORM_BUFFER,
+            (STORAGE_CLASS_STORAGE_BUFFER, Type::Struct { .. }) => DescriptorType::STORAGE_BUFFER,
+            _ => return None,
+        };
+
+        Some(
+            descriptor_count.map(|descriptor_count| DescriptorBindingInfo {
+                set,
+                binding,
+                name: self.variable_name(id, type_id),
+                descriptor_type,
+                descriptor_count,
+            }),
+        )
+    }
+
+    fn specialization_constants(&self) -> Vec<SpecializationConstantInfo> {
+        self.instructions
+            .iter()
+            .filter(|instruction| {
+                (OP_SPEC_CONSTANT_TRUE..=OP_SPEC_CONSTANT).contains(&instruction.opcode)
+            })
+            .filter_map(|instruction| self.specialization_constant(instruction))
+            .collect()
+    }
+
+    /// Reflects `OpSpecConstantTrue`, `OpSpecConstantFalse` or `OpSpecConstant`,
+    /// `None` if the constant isn't decorated with `SpecId`.
+    fn specialization_constant(
+        &self,
+        instruction: &Instruction,
+    ) -> Option<SpecializationConstantInfo> {
+        let (result_type, id, value) = match instruction.operands {
+            [result_type, id, value @ ..] => (*result_type, *id, value),
+            _ => return None,
+        };
+        let constant_id = *self.decoration(id, DECORATION_SPEC_ID)?.first()?;
+
+        let constant_type = match *self.types.get(&result_type)? {
+            Type::Bool => SpecializationConstantType::Bool,
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => SpecializationConstantType::I32,
+            Type::Int { width: 32, .. } => SpecializationConstantType::U32,
+            Type::Float { width: 32 } => SpecializationConstantType::F32,
+            Type::Float { width: 64 } => SpecializationConstantType::F64,
+            Type::Int { width, .. } | Type::Float { width } => SpecializationConstantType::Other {
+                size: width as usize / 8,
+            },
+            // Specialization constants are scalars.
+            _ => return None,
+        };
+
+        let default_value = match (instruction.opcode, constant_type, value) {
+            (OP_SPEC_CONSTANT_TRUE, ..) => Some(SpecializationValue::Bool(true)),
+            (OP_SPEC_CONSTANT_FALSE, ..) => Some(SpecializationValue::Bool(false)),
+            (_, SpecializationConstantType::I32, [word, ..]) => {
+                Some(SpecializationValue::I32(*word as i32))
+            }
+            (_, SpecializationConstantType::U32, [word, ..]) => {
+                Some(SpecializationValue::U32(*word))
+            }
+            (_, SpecializationConstantType::F32, [word, ..]) => {
+                Some(SpecializationValue::F32(f32::from_bits(*word)))
+            }
+            // Literals wider than 32 bits start with the low-order word.
+            (_, SpecializationConstantType::F64, [low, high, ..]) => Some(
+                SpecializationValue::F64(f64::from_bits(u64::from(*low) | u64::from(*high) << 32)),
+            ),
+            _ => None,
+        };
+
+        Some(SpecializationConstantInfo {
+            constant_id,
+            name: self.names.get(&id).cloned(),
+            constant_type,
+            default_value,
+        })
+    }
+}
+
+/// Reflects the interface of the SPIR-V module for the stage of one of its entry points.
+/// `None` if the module is malformed, e.g. its types are cyclic or the sizes of its blocks or vertex inputs overflow.
+pub(crate) fn reflect(words: &[u32], stage: ShaderStageFlags) -> Option<ShaderReflection> {
+    let module = Module::parse(words)?;
+    let entry_points = spirv::entry_points(words)?;
+    // Resources of the module with one entry point are all its own, even the unused ones.
+    let referenced_ids = if entry_points.len() > 1 {
+        module.referenced_ids(&entry_points, stage)
+    } else {
+        None
+    };
+
+    Some(ShaderReflection {
+        specialization_constants: module.specialization_constants(),
+        descriptor_bindings: module.descriptor_bindings(referenced_ids.as_ref()).ok()?,
+        push_constant_blocks: module.push_constant_blocks(referenced_ids.as_ref()).ok()?,
+        vertex_inputs: module.vertex_inputs(&entry_points).ok()?,
+    })
+}
+
+fn checked_mul(a: u32, b: u32) -> Result<u32, Overflow> {
+    a.checked_mul(b).ok_or(Overflow)
+}
+
+/// Format of the vertex attribute of the scalar or the vector with the component type and its width in bits,
+/// `None` for the types that have no format.
+fn vertex_format(component: Type, component_count: u32) -> Option<(Format, u32)> {
+    let (formats, width) = match component {
+        Type::Float { width: 16 } => (
+            [
+                Format::R16_SFLOAT,
+                Format::R16G16_SFLOAT,
+                Format::R16G16B16_SFLOAT,
+                Format::R16G16B16A16_SFLOAT,
+            ],
+            16,
+        ),
+        Type::Float { width: 32 } => (
+            [
+                Format::R32_SFLOAT,
+                Format::R32G32_SFLOAT,
+                Format::R32G32B32_SFLOAT,
+                Format::R32G32B32A32_SFLOAT,
+            ],
+            32,
+        ),
+        Type::Float { width: 64 } => (
+            [
+                Format::R64_SFLOAT,
+                Format::R64G64_SFLOAT,
+                Format::R64G64B64_SFLOAT,
+                Format::R64G64B64A64_SFLOAT,
+            ],
+            64,
+        ),
+        Type::Int { width: 8, signed } => (
+            if signed {
+                [
+                    Format::R8_SINT,
+                    Format::R8G8_SINT,
+                    Format::R8G8B8_SINT,
+                    Format::R8G8B8A8_SINT,
+                ]
+            } else {
+                [
+                    Format::R8_UINT,
+                    Format::R8G8_UINT,
+                    Format::R8G8B8_UINT,
+                    Format::R8G8B8A8_UINT,
+                ]
+            },
+            8,
+        ),
+        Type::Int { width: 16, signed } => (
+            if signed {
+                [
+                    Format::R16_SINT,
+                    Format::R16G16_SINT,
+                    Format::R16G16B16_SINT,
+                    Format::R16G16B16A16_SINT,
+                ]
+            } else {
+                [
+                    Format::R16_UINT,
+                    Format::R16G16_UINT,
+                    Format::R16G16B16_UINT,
+                    Format::R16G16B16A16_UINT,
+                ]
+            },
+            16,
+        ),
+        Type::Int { width: 32, signed } => (
+            if signed {
+                [
+                    Format::R32_SINT,
+                    Format::R32G32_SINT,
+                    Format::R32G32B32_SINT,
+                    Format::R32G32B32A32_SINT,
+                ]
+            } else {
+                [
+                    Format::R32_UINT,
+                    Format::R32G32_UINT,
+                    Format::R32G32B32_UINT,
+                    Format::R32G32B32A32_UINT,
+                ]
+            },
+            32,
+        ),
+        Type::Int { width: 64, signed } => (
+            if signed {
+                [
+                    Format::R64_SINT,
+                    Format::R64G64_SINT,
+                    Format::R64G64B64_SINT,
+                    Format::R64G64B64A64_SINT,
+                ]
+            } else {
+                [
+                    Format::R64_UINT,
+                    Format::R64G64_UINT,
+                    Format::R64G64B64_UINT,
+                    Format::R64G64B64A64_UINT,
+                ]
+            },
+            64,
+        ),
+        _ => return None,
+    };
+
+    let format = *formats.get(component_count.checked_sub(1)? as usize)?;
+    Some((format, width))
+}
+
+/// Merges the descriptor bindings of the stages into the bindings of every set, sorted by the set and the binding.
+/// Stage flags of the binding declared by several stages are merged and its count is the largest of them.
+pub(crate) fn descriptor_set_bindings(
+    records: &[ShaderRecord],
+) -> Result<BTreeMap<u32, Vec<DescriptorSetLayoutBinding>>, ShaderCreatorError> {
+    let mut sets: BTreeMap<u32, Vec<DescriptorSetLayoutBinding>> = BTreeMap::new();
+    for record in records {
+        for descriptor_binding in &record.reflection.descriptor_bindings {
+            let bindings = sets.entry(descriptor_binding.set).or_default();
+            match bindings
+                .iter_mut()
+                .find(|binding| binding.binding == descriptor_binding.binding)
+            {
+                Some(binding) if binding.descriptor_type != descriptor_binding.descriptor_type => {
+                    return Err(ShaderCreatorError::DescriptorTypeMismatch {
+                        path: record.path.clone(),
+                        stage: record.stage,
+                        set: descriptor_binding.set,
+                        binding: descriptor_binding.binding,
+                        expected: binding.descriptor_type,
+                        found: descriptor_binding.descriptor_type,
+                    });
+                }
+                Some(binding) => {
+                    binding.stage_flags |= record.stage;
+                    binding.descriptor_count = binding
+                        .descriptor_count
+                        .max(descriptor_binding.descriptor_count);
+                }
+                None => bindings.push(DescriptorSetLayoutBinding {
+                    binding: descriptor_binding.binding,
+                    descriptor_type: descriptor_binding.descriptor_type,
+                    descriptor_count: descriptor_binding.descriptor_count,
+                    stage_flags: record.stage,
+                    p_immutable_samplers: ptr::null(),
+                }),
+            }
+        }
+    }
+
+    for bindings in sets.values_mut() {
+        bindings.sort_by_key(|binding| binding.binding);
+    }
+
+    Ok(sets)
+}
+
+/// Merges the push constant blocks of the stages into ranges, the stages whose blocks span the same bytes share
+/// one range. The ranges are sorted by the offset and the size.
+pub(crate) fn push_constant_ranges(
+    records: &[ShaderRecord],
+) -> Result<Vec<PushConstantRange>, ShaderCreatorError> {
+    let mut ranges: Vec<PushConstantRange> = Vec::new();
+    // Members of the blocks of the stages merged so far, the overlapping members of the same block in different
+    // stages must be the same member, with the same offset, size and type. Blocks named differently, e.g.
+    // `VSConstants` and `PSConstants` of the module with several entry points, are different blocks.
+    let mut members: Vec<(
+        ShaderStageFlags,
+        &PushConstantBlockInfo,
+        &PushConstantMemberInfo,
+    )> = Vec::new();
+    for record in records {
+        for block in &record.reflection.push_constant_blocks {
+            for member in &block.members {
+                let conflict = members.iter().find(|(stage, other_block, other)| {
+                    *stage != record.stage
+                        && (block.name.is_none()
+                            || other_block.name.is_none()
+                            || block.name == other_block.name)
+                        && member.offset < other.offset + other.size
+                        && other.offset < member.offset + member.size
+                        && ((member.offset, member.size) != (other.offset, other.size)
+                            || member.type_name != other.type_name)
+                });
+                if let Some((stage, _, other)) = conflict {
+                    return Err(ShaderCreatorError::PushConstantMismatch {
+                        path: record.path.clone(),
+                        stage: record.stage,
+                        offset: member.offset,
+                        size: member.size,
+                        type_name: member.type_name.clone(),
+                        other_stage: *stage,
+                        other_offset: other.offset,
+                        other_size: other.size,
+                        other_type_name: other.type_name.clone(),
+                    });
+                }
+            }
+            members.extend(
+                block
+                    .members
+                    .iter()
+                    .map(|member| (record.stage, block, member)),
+            );
+        }
+
+        // Vulkan allows one range per stage, so all blocks of the stage are spanned by one.
+        let blocks = &record.reflection.push_constant_blocks;
+        let offset = match blocks.iter().map(|block| block.offset).min() {
+            Some(offset) => offset,
+            None => continue,
+        };
+        let end = blocks
+            .iter()
+            .map(|block| block.offset + block.size)
+            .max()
+            .unwrap_or(offset);
+        match ranges
+            .iter_mut()
+            .find(|range| (range.offset, range.size) == (offset, end - offset))
+        {
+            Some(range) => range.stage_flags |= record.stage,
+            None => ranges.push(PushConstantRange {
+                stage_flags: record.stage,
+                offset,
+                size: end - offset,
+            }),
+        }
+    }
+
+    ranges.sort_by_key(|range| (range.offset, range.size));
+    Ok(ranges)
+}
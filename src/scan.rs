@@ -0,0 +1,290 @@
+//! Scanning of the directories for the compiled shaders.
+
+use std::{
+    fs::{read_dir, File},
+    io::{ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::{
+    naming::{self, FileNaming},
+    spirv, IgnoreReason, IgnoredFile, NamingConvention, ShaderCreatorError,
+};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// The compiled shader file found in the directory.
+pub(crate) struct ShaderFile {
+    pub(crate) path: PathBuf,
+    pub(crate) naming: FileNaming,
+}
+
+/// Files found in the directory.
+pub(crate) struct ScanResult {
+    pub(crate) shader_files: Vec<ShaderFile>,
+    pub(crate) ignored_files: Vec<IgnoredFile>,
+    /// Compiled shaders and subdirectories to scan whose names aren't valid UTF-8, reported like the invalid
+    /// compiled shaders.
+    pub(crate) failures: Vec<ShaderCreatorError>,
+}
+
+impl ScanResult {
+    fn ignore(&mut self, path: PathBuf, reason: IgnoreReason) {
+        self.ignored_files.push(IgnoredFile { path, reason });
+    }
+}
+
+/// Defines which files and subdirectories of the directories are scanned.
+pub(crate) struct ScanFilter {
+    recursive: bool,
+    skip_hidden: bool,
+    include_patterns: Vec<Pattern>,
+    exclude_patterns: Vec<Pattern>,
+}
+
+impl ScanFilter {
+    pub(crate) fn new(
+        recursive: bool,
+        skip_hidden: bool,
+        include_patterns: &[String],
+        exclude_patterns: &[String],
+    ) -> Result<Self, ShaderCreatorError> {
+        Ok(Self {
+            recursive,
+            skip_hidden,
+            include_patterns: compile_patterns(include_patterns)?,
+            exclude_patterns: compile_patterns(exclude_patterns)?,
+        })
+    }
+
+    /// Collects the compiled shaders in the directory, sorted to keep the order of shaders stable.
+    /// Files that follow none of the naming conventions and aren't `.spv` files are skipped,
+    /// as well as the files that follow one, but are neither `.spv` files nor start with the SPIR-V magic number.
+    pub(crate) fn scan_dir(
+        &self,
+        dir_path: &Path,
+        naming_conventions: &[Box<dyn NamingConvention>],
+    ) -> Result<ScanResult, ShaderCreatorError> {
+        let mut result = ScanResult {
+            shader_files: Vec::new(),
+            ignored_files: Vec::new(),
+            failures: Vec::new(),
+        };
+        let mut ancestors = vec![canonical_path(dir_path)];
+        self.scan(
+            dir_path,
+            Path::new(""),
+            naming_conventions,
+            &mut ancestors,
+            &mut result,
+        )?;
+        result.shader_files.sort_by(|a, b| a.path.cmp(&b.path));
+        result.ignored_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(result)
+    }
+
+    /// `ancestors` are the canonical paths of the directories being scanned, so a symbolic link
+    /// to one of them isn't followed into an endless recursion.
+    fn scan(
+        &self,
+        dir_path: &Path,
+        relative_dir_path: &Path,
+        naming_conventions: &[Box<dyn NamingConvention>],
+        ancestors: &mut Vec<PathBuf>,
+        result: &mut ScanResult,
+    ) -> Result<(), ShaderCreatorError> {
+        let read_directory_error = |source| ShaderCreatorError::ReadDirectory {
+            path: dir_path.to_path_buf(),
+            source,
+        };
+
+        for entry in read_dir(dir_path).map_err(read_directory_error)? {
+            let entry = entry.map_err(read_directory_error)?;
+            let path = entry.path();
+            // Names that aren't valid UTF-8 are filtered by their lossy form, and fail only if they would be
+            // loaded as compiled shaders or scanned as subdirectories.
+            let os_file_name = entry.file_name();
+            let is_utf8 = os_file_name.to_str().is_some();
+            let file_name = os_file_name.to_string_lossy();
+
+            let relative_path = relative_dir_path.join(&*file_name);
+            if self.skip_hidden && file_name.starts_with('.') {
+                result.ignore(path, IgnoreReason::Hidden);
+                continue;
+            }
+
+            if self.matches_any(&self.exclude_patterns, &relative_path) {
+                result.ignore(path, IgnoreReason::Excluded);
+                continue;
+            }
+
+            if path.is_dir() {
+                if !self.recursive {
+                    result.ignore(path, IgnoreReason::NotRecursive);
+                    continue;
+                }
+
+                if !is_utf8 {
+                    result
+                        .failures
+                        .push(ShaderCreatorError::NonUtf8Path { path });
+                    continue;
+                }
+
+                let canonical_dir_path = canonical_path(&path);
+                if ancestors.contains(&canonical_dir_path) {
+                    result.ignore(path, IgnoreReason::Cycle);
+                    continue;
+                }
+
+                ancestors.push(canonical_dir_path);
+                self.scan(&path, &relative_path, naming_conventions, ancestors, result)?;
+                ancestors.pop();
+                continue;
+            }
+
+            if !self.include_patterns.is_empty()
+                && !self.matches_any(&self.include_patterns, &relative_path)
+            {
+                result.ignore(path, IgnoreReason::NotIncluded);
+                continue;
+            }
+
+            // Stage suffixes like `.cs` or `.ps` are common extensions of other files, e.g. `Program.cs`.
+            match naming::resolve(naming_conventions, &relative_path) {
+                Some(naming) if is_spv_file(&file_name) || starts_with_spirv_magic(&path) => {
+                    if is_utf8 {
+                        result.shader_files.push(ShaderFile { path, naming });
+                    } else {
+                        result
+                            .failures
+                            .push(ShaderCreatorError::NonUtf8Path { path });
+                    }
+                }
+                _ => result.ignore(path, IgnoreReason::NotShader),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Patterns without `/` are matched against the file name, the others against the relative path.
+    fn matches_any(&self, patterns: &[Pattern], relative_path: &Path) -> bool {
+        let relative_path = naming::slash_path(relative_path);
+        let file_name = relative_path.rsplit('/').next().unwrap_or_default();
+
+        patterns.iter().any(|pattern| {
+            let path = if pattern.as_str().contains('/') {
+                relative_path.as_str()
+            } else {
+                file_name
+            };
+
+            pattern.matches_with(path, MATCH_OPTIONS)
+        })
+    }
+}
+
+fn is_spv_file(file_name: &str) -> bool {
+    let extension = file_name.rsplit('.').next().unwrap_or_default();
+    extension.eq_ignore_ascii_case("spv")
+}
+
+/// Whether the file starts with the SPIR-V magic number in any endianness. Files that can't be read are
+/// treated as SPIR-V, so the read error is reported by the loader.
+fn starts_with_spirv_magic(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    match File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+        Ok(()) => {
+            let magic = u32::from_ne_bytes(magic);
+            magic == spirv::MAGIC_NUMBER || magic.swap_bytes() == spirv::MAGIC_NUMBER
+        }
+        Err(error) => error.kind() != ErrorKind::UnexpectedEof,
+    }
+}
+
+/// Resolves the symbolic links of the directory path, the path itself if it can't be resolved.
+fn canonical_path(dir_path: &Path) -> PathBuf {
+    dir_path
+        .canonicalize()
+        .unwrap_or_else(|_| dir_path.to_path_buf())
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>, ShaderCreatorError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|source| ShaderCreatorError::InvalidGlobPattern {
+                pattern: pattern.clone(),
+                source,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::spirv;
+
+    #[test]
+    fn compiled_shaders_are_collected_in_sorted_order() {
+        let dir_path = env::temp_dir().join(format!("ash_shader_creator_scan_{}", process::id()));
+        fs::create_dir_all(&dir_path).unwrap();
+        for file_name in [
+            "sky.frag.spv",
+            "notes.txt",
+            "sky.vs",
+            "cull.spv",
+            "sky.frag",
+        ] {
+            fs::write(dir_path.join(file_name), spirv::MAGIC_NUMBER.to_ne_bytes()).unwrap();
+        }
+
+        let result = ScanFilter::new(false, true, &[], &[])
+            .unwrap()
+            .scan_dir(&dir_path, &naming::default_naming_conventions());
+        fs::remove_dir_all(&dir_path).unwrap();
+
+        let result = result.unwrap();
+        let file_names: Vec<_> = result
+            .shader_files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.strip_prefix(&dir_path).unwrap().to_path_buf(),
+                    file.naming.program_name.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            file_names,
+            [
+                (PathBuf::from("cull.spv"), String::from("cull")),
+                (PathBuf::from("sky.frag.spv"), String::from("sky")),
+                (PathBuf::from("sky.vs"), String::from("sky")),
+            ]
+        );
+        let ignored_files: Vec<_> = result
+            .ignored_files
+            .iter()
+            .map(|file| (file.path.strip_prefix(&dir_path).unwrap(), file.reason))
+            .collect();
+        assert_eq!(
+            ignored_files,
+            [
+                (Path::new("notes.txt"), IgnoreReason::NotShader),
+                (Path::new("sky.frag"), IgnoreReason::NotShader),
+            ]
+        );
+    }
+}
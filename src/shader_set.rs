@@ -0,0 +1,414 @@
+//! Shader modules created by `ShaderStage` and the shader stages that use them.
+
+use std::{collections::BTreeMap, ffi::CString, marker::PhantomData};
+
+use ash::{
+    vk::{
+        AllocationCallbacks, DescriptorSetLayoutBinding, PipelineShaderStageCreateInfo,
+        PushConstantRange,
+    },
+    Device,
+};
+
+use crate::{
+    loader, pipeline_layout, reflection, specialization::OwnedSpecializationInfo, vertex_input,
+    InvalidShader, PipelineLayoutConfig, ProgramLayout, ShaderCreatorError, ShaderDevice,
+    ShaderRecord, VertexInputConfig, VertexInputDescriptions,
+};
+
+/// Owns the shader modules created by `ShaderStage::build` and the shader stages that use them.
+///
+/// The set also owns the entry point names and the specialization info the stages point to,
+/// so the stages can be used only while the set lives.
+///
+/// Shader modules are needed only until the pipelines are created, so the set can be destroyed right after that
+/// with `destroy`. If the set was built with `ShaderStage::with_destroy_on_drop`, the modules are destroyed on drop.
+/// Layouts created by `ShaderProgram::create_pipeline_layout` aren't owned by the set and outlive it.
+pub struct ShaderSet<'a, D: ShaderDevice = Device> {
+    records: Vec<ShaderRecord>,
+    stages: Vec<PipelineShaderStageCreateInfo>,
+    failures: Vec<InvalidShader>,
+    // Only keep alive the data `stages` point to.
+    _entry_point_names: Vec<CString>,
+    _specialization_infos: Vec<OwnedSpecializationInfo>,
+    allocation_callbacks: Option<&'a AllocationCallbacks>,
+    drop_device: Option<&'a D>,
+}
+
+impl<'a, D: ShaderDevice> ShaderSet<'a, D> {
+    /// `records` and `stages` must be index-aligned and sorted by the program name and variant, `p_name` and
+    /// `p_specialization_info` of the stages must point into `entry_point_names` and `specialization_infos`.
+    pub(crate) fn new(
+        records: Vec<ShaderRecord>,
+        stages: Vec<PipelineShaderStageCreateInfo>,
+        failures: Vec<InvalidShader>,
+        entry_point_names: Vec<CString>,
+        specialization_infos: Vec<OwnedSpecializationInfo>,
+        allocation_callbacks: Option<&'a AllocationCallbacks>,
+        drop_device: Option<&'a D>,
+    ) -> Self {
+        Self {
+            records,
+            stages,
+            failures,
+            _entry_point_names: entry_point_names,
+            _specialization_infos: specialization_infos,
+            allocation_callbacks,
+            drop_device,
+        }
+    }
+
+    /// Records of the compiled shaders the shader modules were created from.
+    pub fn records(&self) -> &[ShaderRecord] {
+        &self.records
+    }
+
+    /// All shader stages, grouped by programs and sorted in the pipeline order inside every program.
+    /// Entry point names and specialization info of the stages are valid as long as the set lives.
+    pub fn stages(&self) -> &[PipelineShaderStageCreateInfo] {
+        &self.stages
+    }
+
+    /// Compiled shaders skipped because of their errors, always empty unless built with `ErrorPolicy::Collect`.
+    pub fn failures(&self) -> &[InvalidShader] {
+        &self.failures
+    }
+
+    /// The program without a variant, `None` if there is no program with such name.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::ShaderStage;
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let sky_stages = shader_set.program("sky").expect("No sky shaders!").stages();
+    /// // Create the pipeline with `sky_stages`.
+    /// shader_set.destroy(device);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn program(&self, name: &str) -> Option<ShaderProgram<'_, D>> {
+        self.programs()
+            .into_iter()
+            .find(|program| program.name() == name && program.variant().is_none())
+    }
+
+    /// The variant of the program, `None` if there is no such program or variant.
+    pub fn program_variant(&self, name: &str, variant: &str) -> Option<ShaderProgram<'_, D>> {
+        self.programs()
+            .into_iter()
+            .find(|program| program.name() == name && program.variant() == Some(variant))
+    }
+
+    /// Every program and every its variant, sorted by the name and the variant.
+    pub fn programs(&self) -> Vec<ShaderProgram<'_, D>> {
+        let mut programs = Vec::new();
+        let mut start = 0;
+        while start < self.records.len() {
+            let first = &self.records[start];
+            let len = self.records[start..]
+                .iter()
+                .take_while(|record| {
+                    record.program_name == first.program_name && record.variant == first.variant
+                })
+                .count();
+            programs.push(ShaderProgram {
+                records: &self.records[start..start + len],
+                stages: &self.stages[start..start + len],
+                allocation_callbacks: self.allocation_callbacks,
+                _device: PhantomData,
+            });
+            start += len;
+        }
+
+        programs
+    }
+
+    /// Consumes the set and destroys all its shader modules with the `AllocationCallbacks` they were created with.
+    /// The layouts created from its programs are left alive.
+    pub fn destroy(mut self, device: &D) {
+        self.destroy_objects(device);
+    }
+
+    fn destroy_objects(&mut self, device: &D) {
+        self.stages.clear();
+        loader::destroy_shader_modules(device, &self.records, self.allocation_callbacks);
+        self.records.clear();
+    }
+}
+
+impl<D: ShaderDevice> Drop for ShaderSet<'_, D> {
+    fn drop(&mut self) {
+        if let Some(device) = self.drop_device {
+            self.destroy_objects(device);
+        }
+    }
+}
+
+/// Shader stages of one program of the `ShaderSet`, sorted in the pipeline order.
+pub struct ShaderProgram<'s, D: ShaderDevice = Device> {
+    records: &'s [ShaderRecord],
+    stages: &'s [PipelineShaderStageCreateInfo],
+    allocation_callbacks: Option<&'s AllocationCallbacks>,
+    // The device type of the set, which creates the layouts of the program.
+    _device: PhantomData<&'s D>,
+}
+
+// Derives would require `D: Copy`.
+impl<D: ShaderDevice> Clone for ShaderProgram<'_, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D: ShaderDevice> Copy for ShaderProgram<'_, D> {}
+
+impl<'s, D: ShaderDevice> ShaderProgram<'s, D> {
+    pub fn name(&self) -> &'s str {
+        &self.records[0].program_name
+    }
+
+    pub fn variant(&self) -> Option<&'s str> {
+        self.records[0].variant.as_deref()
+    }
+
+    /// Records of the compiled shaders of the program.
+    pub fn records(&self) -> &'s [ShaderRecord] {
+        self.records
+    }
+
+    /// Shader stages of the program, ready for its own pipeline.
+    pub fn stages(&self) -> &'s [PipelineShaderStageCreateInfo] {
+        self.stages
+    }
+
+    /// Bindings of every descriptor set the program uses, reflected from its stages and sorted by the binding.
+    /// The binding used by several stages has their stage flags merged, the bindings have no immutable samplers.
+    ///
+    /// Fails with `DescriptorTypeMismatch` if the stages declare the same binding with different descriptor types.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::ShaderStage;
+    /// use ash::vk;
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let sky = shader_set.program("sky").expect("No sky shaders!");
+    /// for (set, bindings) in sky.descriptor_set_bindings()? {
+    ///     let create_info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+    ///     // Create the layout of the descriptor set `set` with `create_info`.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn descriptor_set_bindings(
+        &self,
+    ) -> Result<BTreeMap<u32, Vec<DescriptorSetLayoutBinding>>, ShaderCreatorError> {
+        reflection::descriptor_set_bindings(self.records)
+    }
+
+    /// Push constant ranges of the program, reflected from its stages and sorted by the offset.
+    /// Stages whose push constant blocks span the same bytes share one range.
+    ///
+    /// Fails with `PushConstantMismatch` if members of the same block in different stages partially overlap.
+    pub fn push_constant_ranges(&self) -> Result<Vec<PushConstantRange>, ShaderCreatorError> {
+        reflection::push_constant_ranges(self.records)
+    }
+
+    /// Vertex buffer bindings and vertex attributes of the program, reflected from the inputs of its vertex stage
+    /// and assigned to the bindings by the config. Empty if the program has no vertex stage.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{ShaderStage, VertexBindingAssignment, VertexInputConfig};
+    /// use ash::vk;
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// // Per-vertex attributes are interleaved in the binding 0, the instance transform at the location 4
+    /// // is in the binding 1.
+    /// let config = VertexInputConfig::new()
+    ///     .with_assignment(VertexBindingAssignment::Interleaved)
+    ///     .with_location_binding(4, 1)
+    ///     .with_input_rate(1, vk::VertexInputRate::INSTANCE);
+    ///
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let sky = shader_set.program("sky").expect("No sky shaders!");
+    /// let vertex_input = sky.vertex_input_descriptions(&config);
+    /// let vertex_input_state = vertex_input.create_info();
+    /// // Create the pipeline with `vertex_input_state`.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn vertex_input_descriptions(&self, config: &VertexInputConfig) -> VertexInputDescriptions {
+        vertex_input::vertex_input_descriptions(self.records, config)
+    }
+
+    /// Creates the descriptor set layouts and the pipeline layout of the program from `descriptor_set_bindings`
+    /// and `push_constant_ranges`, with the `AllocationCallbacks` of the set. `device` must be the one the set
+    /// was built with. The returned layout owns the created layouts, which outlive the set and its shader modules,
+    /// and must be destroyed with `ProgramLayout::destroy` after the pipelines that use them.
+    /// # Errors
+    ///
+    /// Returns `ShaderCreatorError::UnresolvedDescriptorCount` for a runtime array or an array of unresolved length,
+    /// whose count has to be set with `create_pipeline_layout_with_config`.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::ShaderStage;
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    ///
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let sky = shader_set.program("sky").expect("No sky shaders!");
+    /// let sky_layout = sky.create_pipeline_layout(device)?;
+    /// // Create the pipeline with `sky.stages()` and `sky_layout.pipeline_layout`.
+    /// shader_set.destroy(device);
+    /// // Destroy the pipeline.
+    /// sky_layout.destroy(device);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_pipeline_layout(&self, device: &D) -> Result<ProgramLayout, ShaderCreatorError> {
+        self.create_pipeline_layout_with_config(device, &PipelineLayoutConfig::new())
+    }
+
+    /// Like `create_pipeline_layout`, with the reflected bindings and descriptor set layout flags
+    /// overridden by the config.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash_shader_creator::{BindingConfig, PipelineLayoutConfig, ShaderStage};
+    /// use ash::vk;
+    /// use std::path::Path;
+    /// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+    /// # let shadow_sampler = vk::Sampler::null();
+    ///
+    /// let config = PipelineLayoutConfig::new()
+    ///     .with_binding(
+    ///         0,
+    ///         0,
+    ///         BindingConfig::new().with_descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC),
+    ///     )
+    ///     .with_binding(1, 0, BindingConfig::new().with_immutable_samplers(vec![shadow_sampler]))
+    ///     .with_binding(
+    ///         2,
+    ///         0,
+    ///         BindingConfig::new().with_binding_flags(vk::DescriptorBindingFlags::UPDATE_AFTER_BIND),
+    ///     )
+    ///     .with_set_flags(2, vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL);
+    ///
+    /// let shader_set = ShaderStage::new(device, Path::new("example_path/compiled_shaders")).build()?;
+    /// let sky = shader_set.program("sky").expect("No sky shaders!");
+    /// let sky_layout = sky.create_pipeline_layout_with_config(device, &config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_pipeline_layout_with_config(
+        &self,
+        device: &D,
+        config: &PipelineLayoutConfig,
+    ) -> Result<ProgramLayout, ShaderCreatorError> {
+        pipeline_layout::create_program_layout(
+            device,
+            self.records,
+            config,
+            self.allocation_callbacks,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ash::vk::{ShaderModule, ShaderStageFlags};
+
+    use super::*;
+
+    /// Set of records sorted like `ShaderStage::build` sorts them.
+    fn shader_set() -> ShaderSet<'static> {
+        let records: Vec<ShaderRecord> = [
+            ("cull.spv", "cull", None, ShaderStageFlags::COMPUTE),
+            ("sky.vert.spv", "sky", None, ShaderStageFlags::VERTEX),
+            ("sky.frag.spv", "sky", None, ShaderStageFlags::FRAGMENT),
+            (
+                "sky.shadow.vert.spv",
+                "sky",
+                Some("shadow"),
+                ShaderStageFlags::VERTEX,
+            ),
+        ]
+        .iter()
+        .map(|(path, program_name, variant, stage)| ShaderRecord {
+            path: PathBuf::from(path),
+            program_name: String::from(*program_name),
+            variant: variant.map(String::from),
+            stage: *stage,
+            entry_point: String::from("main"),
+            module: ShaderModule::null(),
+            code_hash: 0,
+            reflection: Default::default(),
+        })
+        .collect();
+        let stages = records
+            .iter()
+            .map(|record| PipelineShaderStageCreateInfo {
+                stage: record.stage,
+                ..Default::default()
+            })
+            .collect();
+
+        ShaderSet::new(
+            records,
+            stages,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            None,
+            None,
+        )
+    }
+
+    fn stage_flags(program: ShaderProgram<'_>) -> Vec<ShaderStageFlags> {
+        program.stages().iter().map(|stage| stage.stage).collect()
+    }
+
+    #[test]
+    fn stages_are_looked_up_by_program() {
+        let shader_set = shader_set();
+
+        assert_eq!(
+            stage_flags(shader_set.program("sky").unwrap()),
+            [ShaderStageFlags::VERTEX, ShaderStageFlags::FRAGMENT]
+        );
+        assert_eq!(
+            stage_flags(shader_set.program_variant("sky", "shadow").unwrap()),
+            [ShaderStageFlags::VERTEX]
+        );
+        assert!(shader_set.program("sea").is_none());
+        assert!(shader_set.program_variant("cull", "shadow").is_none());
+
+        let programs: Vec<_> = shader_set
+            .programs()
+            .into_iter()
+            .map(|program| (program.name(), program.variant(), stage_flags(program)))
+            .collect();
+        assert_eq!(
+            programs,
+            [
+                ("cull", None, vec![ShaderStageFlags::COMPUTE]),
+                (
+                    "sky",
+                    None,
+                    vec![ShaderStageFlags::VERTEX, ShaderStageFlags::FRAGMENT]
+                ),
+                ("sky", Some("shadow"), vec![ShaderStageFlags::VERTEX]),
+            ]
+        );
+    }
+}
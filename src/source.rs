@@ -0,0 +1,139 @@
+//! Compiled shaders that are already in memory.
+
+use std::{io::Read, path::PathBuf};
+
+use ash::vk::ShaderStageFlags;
+
+use crate::{spirv, ShaderCreatorError};
+
+/// The compiled shader in memory, e.g. embedded with `include_bytes!` or received from an asset system.
+///
+/// `name` is used like the file name of a compiled shader in the directory: it defines the program the shader
+/// belongs to and is used in errors. If `stage_hint` is set, it's used instead of the naming conventions of `name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderSource {
+    pub name: String,
+    pub stage_hint: Option<ShaderStageFlags>,
+    code: ShaderCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShaderCode {
+    Bytes(Vec<u8>),
+    Words(Vec<u32>),
+}
+
+impl ShaderSource {
+    /// Creates the source from bytes of the compiled shader, they don't have to be aligned.
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ash::vk::ShaderStageFlags;
+    /// use ash_shader_creator::ShaderSource;
+    /// # let compiled_shader: &[u8] = &[];
+    ///
+    /// // `compiled_shader` is e.g. `include_bytes!("compiled_shaders/sky.vert.spv")`.
+    /// let source = ShaderSource::from_bytes("sky.vert.spv", Some(ShaderStageFlags::VERTEX), compiled_shader);
+    /// ```
+    pub fn from_bytes(name: &str, stage_hint: Option<ShaderStageFlags>, bytes: &[u8]) -> Self {
+        Self {
+            name: name.to_owned(),
+            stage_hint,
+            code: ShaderCode::Bytes(bytes.to_vec()),
+        }
+    }
+
+    /// Creates the source from words of the compiled shader.
+    pub fn from_words(name: &str, stage_hint: Option<ShaderStageFlags>, words: &[u32]) -> Self {
+        Self {
+            name: name.to_owned(),
+            stage_hint,
+            code: ShaderCode::Words(words.to_vec()),
+        }
+    }
+
+    /// Creates the source from the compiled shader read to the end from the `reader`.
+    /// # Errors
+    ///
+    /// Returns `ShaderCreatorError::ReadFile` with `name` as the path if the `reader` fails.
+    pub fn from_reader<R: Read>(
+        name: &str,
+        stage_hint: Option<ShaderStageFlags>,
+        mut reader: R,
+    ) -> Result<Self, ShaderCreatorError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|source| ShaderCreatorError::ReadFile {
+                path: PathBuf::from(name),
+                source,
+            })?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            stage_hint,
+            code: ShaderCode::Bytes(bytes),
+        })
+    }
+
+    /// Converts the compiled shader to the validated SPIR-V words.
+    pub(crate) fn words(&self) -> Result<Vec<u32>, ShaderCreatorError> {
+        match &self.code {
+            ShaderCode::Bytes(bytes) => spirv::words_from_bytes(bytes),
+            ShaderCode::Words(words) => spirv::normalize_words(words.clone()),
+        }
+        .map_err(|invalid_spirv| invalid_spirv.into_error(PathBuf::from(&self.name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, path::Path};
+
+    use super::*;
+
+    /// SPIR-V header of an empty module.
+    const HEADER: [u32; 5] = [spirv::MAGIC_NUMBER, 0x0001_0000, 0, 1, 0];
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "disconnected"))
+        }
+    }
+
+    #[test]
+    fn bytes_words_and_readers_give_the_same_words() {
+        let mut bytes = vec![0];
+        bytes.extend(HEADER.iter().flat_map(|word| word.to_ne_bytes()));
+        let unaligned_bytes = &bytes[1..];
+        let swapped_words: Vec<u32> = HEADER.iter().map(|word| word.swap_bytes()).collect();
+
+        let sources = [
+            ShaderSource::from_bytes("sky.vert.spv", None, unaligned_bytes),
+            ShaderSource::from_words("sky.vert.spv", None, &swapped_words),
+            ShaderSource::from_reader("sky.vert.spv", None, unaligned_bytes).unwrap(),
+        ];
+        for source in &sources {
+            assert_eq!(source.words().unwrap(), HEADER);
+        }
+    }
+
+    #[test]
+    fn errors_use_the_name_as_the_path() {
+        let error = ShaderSource::from_reader("sky.vert.spv", None, FailingReader)
+            .expect_err("the reader fails");
+        assert!(
+            matches!(error, ShaderCreatorError::ReadFile { path, .. } if path == Path::new("sky.vert.spv"))
+        );
+
+        let error = ShaderSource::from_words("sky.frag.spv", None, &HEADER[..4])
+            .words()
+            .expect_err("the header is truncated");
+        assert!(matches!(
+            error,
+            ShaderCreatorError::InvalidSpirvSize { path, size: 16 } if path == Path::new("sky.frag.spv")
+        ));
+    }
+}
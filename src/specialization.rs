@@ -0,0 +1,246 @@
+//! Specialization constants of the shader stages.
+
+use std::{ptr, slice};
+
+use ash::vk::{SpecializationInfo, SpecializationMapEntry};
+
+use crate::{ShaderCreatorError, ShaderRecord};
+
+/// Value of the specialization constant, packed with the size of its SPIR-V type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecializationValue {
+    /// Packed as the 4 bytes `VkBool32`.
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    F64(f64),
+}
+
+impl SpecializationValue {
+    fn bytes(self) -> Vec<u8> {
+        match self {
+            Self::Bool(value) => (value as u32).to_ne_bytes().to_vec(),
+            Self::I32(value) => value.to_ne_bytes().to_vec(),
+            Self::U32(value) => value.to_ne_bytes().to_vec(),
+            Self::F32(value) => value.to_ne_bytes().to_vec(),
+            Self::F64(value) => value.to_ne_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<bool> for SpecializationValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for SpecializationValue {
+    fn from(value: i32) -> Self {
+        Self::I32(value)
+    }
+}
+
+impl From<u32> for SpecializationValue {
+    fn from(value: u32) -> Self {
+        Self::U32(value)
+    }
+}
+
+impl From<f32> for SpecializationValue {
+    fn from(value: f32) -> Self {
+        Self::F32(value)
+    }
+}
+
+impl From<f64> for SpecializationValue {
+    fn from(value: f64) -> Self {
+        Self::F64(value)
+    }
+}
+
+/// Values of the specialization constants by their `constant_id`, which own their map entries and packed data.
+/// The `SpecializationInfo` made of them lives as long as the `ShaderSet` built with them.
+/// # Examples
+///
+/// ```rust,no_run
+/// use ash_shader_creator::{ShaderSet, ShaderStage, SpecializationConstants};
+/// use std::path::Path;
+/// # fn example(device: &ash::Device) -> Result<(), ash_shader_creator::ShaderCreatorError> {
+///
+/// let shader_set: ShaderSet =
+///    ShaderStage::new(device, Path::new("example_path/compiled_shaders"))
+///        .with_specialization_constants(
+///            SpecializationConstants::new()
+///                .set(0, true)
+///                .set(1, 64u32)
+///                .set(2, 0.5f32),
+///        )
+///        .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpecializationConstants {
+    constants: Vec<(u32, SpecializationValue)>,
+}
+
+impl SpecializationConstants {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the specialization constant with the `constant_id`, replacing the previous value.
+    pub fn set(mut self, constant_id: u32, value: impl Into<SpecializationValue>) -> Self {
+        let value = value.into();
+        match self.constants.iter_mut().find(|(id, _)| *id == constant_id) {
+            Some((_, previous_value)) => *previous_value = value,
+            None => self.constants.push((constant_id, value)),
+        }
+        self
+    }
+
+    /// The value of the specialization constant with the `constant_id`.
+    pub fn get(&self, constant_id: u32) -> Option<SpecializationValue> {
+        self.constants
+            .iter()
+            .find(|(id, _)| *id == constant_id)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty()
+    }
+
+    /// Packs the values in the order they were set, every value is aligned to its size.
+    pub(crate) fn to_owned_info(&self) -> OwnedSpecializationInfo {
+        let mut map_entries = Vec::with_capacity(self.constants.len());
+        let mut data = Vec::new();
+        for (constant_id, value) in &self.constants {
+            let bytes = value.bytes();
+            let offset = (data.len() + bytes.len() - 1) / bytes.len() * bytes.len();
+            data.resize(offset, 0);
+            data.extend_from_slice(&bytes);
+            map_entries.push(SpecializationMapEntry {
+                constant_id: *constant_id,
+                offset: offset as u32,
+                size: bytes.len(),
+            });
+        }
+
+        OwnedSpecializationInfo::new(map_entries, data)
+    }
+}
+
+/// Copy of the `SpecializationInfo` which owns its map entries and data, so the pointers to it stay valid
+/// as long as the copy lives.
+pub(crate) struct OwnedSpecializationInfo {
+    map_entries: Vec<SpecializationMapEntry>,
+    // Only keep alive the data `info` points to.
+    _data: Vec<u8>,
+    info: Box<SpecializationInfo>,
+}
+
+impl OwnedSpecializationInfo {
+    /// Copies map entries and data of the `SpecializationInfo`.
+    /// # Safety
+    ///
+    /// `p_map_entries` and `p_data` of the `spec_info` must point to `map_entry_count` entries and `data_size` bytes.
+    pub(crate) unsafe fn copy_from(spec_info: &SpecializationInfo) -> Self {
+        let map_entries = if spec_info.map_entry_count == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(spec_info.p_map_entries, spec_info.map_entry_count as usize)
+                .to_vec()
+        };
+        let data = if spec_info.data_size == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(spec_info.p_data as *const u8, spec_info.data_size).to_vec()
+        };
+
+        Self::new(map_entries, data)
+    }
+
+    pub(crate) fn new(map_entries: Vec<SpecializationMapEntry>, data: Vec<u8>) -> Self {
+        // Vectors and the boxed info keep their heap addresses when the struct is moved.
+        let info = Box::new(SpecializationInfo {
+            map_entry_count: map_entries.len() as u32,
+            p_map_entries: map_entries.as_ptr(),
+            data_size: data.len(),
+            p_data: if data.is_empty() {
+                ptr::null()
+            } else {
+                data.as_ptr().cast()
+            },
+        });
+
+        Self {
+            map_entries,
+            _data: data,
+            info,
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const SpecializationInfo {
+        &*self.info
+    }
+
+    /// Checks that every map entry sets the constant the module of the stage declares, with the size of its type.
+    /// Values of `specialization_constants` the info was packed from are checked against the type too.
+    /// `program_records` are given for the defaults of every stage, whose constants are unknown only if no stage
+    /// of the program declares them, even if the program declares no constants at all.
+    /// Vulkan ignores the map entries of the constants the module doesn't declare, so the map entries of the raw
+    /// `SpecializationInfo` are unknown only if the module declares other constants.
+    pub(crate) fn validate(
+        &self,
+        specialization_constants: Option<&SpecializationConstants>,
+        record: &ShaderRecord,
+        program_records: Option<&[ShaderRecord]>,
+    ) -> Result<(), ShaderCreatorError> {
+        for map_entry in &self.map_entries {
+            let constant_id = map_entry.constant_id;
+            let declared = match record.reflection.specialization_constant(constant_id) {
+                Some(declared) => declared,
+                None => {
+                    let declared_by_program =
+                        program_records.into_iter().flatten().any(|program_record| {
+                            program_record.program_name == record.program_name
+                                && program_record.variant == record.variant
+                                && program_record
+                                    .reflection
+                                    .specialization_constant(constant_id)
+                                    .is_some()
+                        });
+                    let raw_info_ignored = specialization_constants.is_none()
+                        && record.reflection.specialization_constants.is_empty();
+                    if declared_by_program || raw_info_ignored {
+                        continue;
+                    }
+
+                    return Err(ShaderCreatorError::UnknownSpecializationConstant {
+                        path: record.path.clone(),
+                        stage: record.stage,
+                        constant_id,
+                    });
+                }
+            };
+
+            let value = specialization_constants
+                .and_then(|specialization_constants| specialization_constants.get(constant_id));
+            let type_matches = value.map_or(true, |value| declared.constant_type.accepts(value));
+            if map_entry.size != declared.constant_type.size() || !type_matches {
+                return Err(ShaderCreatorError::SpecializationConstantMismatch {
+                    path: record.path.clone(),
+                    stage: record.stage,
+                    constant_id,
+                    expected: declared.constant_type,
+                    size: map_entry.size,
+                    value,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+//! A minimal SPIR-V scanner, it reads only the instructions the library needs.
+
+use std::path::PathBuf;
+
+use ash::vk::ShaderStageFlags;
+
+use crate::ShaderCreatorError;
+
+/// The first word of every SPIR-V module.
+pub(crate) const MAGIC_NUMBER: u32 = 0x0723_0203;
+
+/// Count of words in the SPIR-V module header.
+const HEADER_LEN: usize = 5;
+
+const OP_ENTRY_POINT: u32 = 15;
+
+/// `OpEntryPoint` of the SPIR-V module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EntryPoint {
+    pub(crate) execution_model: u32,
+    pub(crate) name: String,
+    /// Id of the `OpFunction` the entry point starts with.
+    pub(crate) function: u32,
+    /// Ids of the global variables the entry point uses, only `Input` and `Output` ones before SPIR-V 1.4.
+    pub(crate) interface: Vec<u32>,
+}
+
+impl EntryPoint {
+    /// Maps the `ExecutionModel` of the entry point to the shader stage, `None` for the models
+    /// that have no Vulkan shader stage, like `Kernel`.
+    pub(crate) fn stage(&self) -> Option<ShaderStageFlags> {
+        let stage = match self.execution_model {
+            0 => ShaderStageFlags::VERTEX,
+            1 => ShaderStageFlags::TESSELLATION_CONTROL,
+            2 => ShaderStageFlags::TESSELLATION_EVALUATION,
+            3 => ShaderStageFlags::GEOMETRY,
+            4 => ShaderStageFlags::FRAGMENT,
+            5 => ShaderStageFlags::COMPUTE,
+            // TaskNV and TaskEXT.
+            5267 | 5364 => ShaderStageFlags::TASK_NV,
+            // MeshNV and MeshEXT.
+            5268 | 5365 => ShaderStageFlags::MESH_NV,
+            5313 => ShaderStageFlags::RAYGEN_KHR,
+            5314 => ShaderStageFlags::INTERSECTION_KHR,
+            5315 => ShaderStageFlags::ANY_HIT_KHR,
+            5316 => ShaderStageFlags::CLOSEST_HIT_KHR,
+            5317 => ShaderStageFlags::MISS_KHR,
+            5318 => ShaderStageFlags::CALLABLE_KHR,
+            _ => return None,
+        };
+
+        Some(stage)
+    }
+}
+
+/// Reasons why bytes can't be converted to the SPIR-V module words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InvalidSpirv {
+    /// Size in bytes isn't a multiple of 4 or is smaller than the header.
+    Size(usize),
+    /// The first word isn't the SPIR-V magic number in any endianness.
+    Magic(u32),
+}
+
+impl InvalidSpirv {
+    pub(crate) fn into_error(self, path: PathBuf) -> ShaderCreatorError {
+        match self {
+            Self::Size(size) => ShaderCreatorError::InvalidSpirvSize { path, size },
+            Self::Magic(magic) => ShaderCreatorError::InvalidSpirvMagic { path, magic },
+        }
+    }
+}
+
+/// Converts bytes of the SPIR-V module to properly aligned words, swapping them if the module was written with
+/// the opposite endianness.
+pub(crate) fn words_from_bytes(bytes: &[u8]) -> Result<Vec<u32>, InvalidSpirv> {
+    let chunks = bytes.chunks_exact(4);
+    if !chunks.remainder().is_empty() || bytes.len() < HEADER_LEN * 4 {
+        return Err(InvalidSpirv::Size(bytes.len()));
+    }
+
+    let words = chunks
+        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    normalize_words(words)
+}
+
+/// Checks the magic number of the SPIR-V module words, swapping them if the module was written with
+/// the opposite endianness.
+pub(crate) fn normalize_words(words: Vec<u32>) -> Result<Vec<u32>, InvalidSpirv> {
+    if words.len() < HEADER_LEN {
+        return Err(InvalidSpirv::Size(words.len() * 4));
+    }
+
+    match words[0] {
+        MAGIC_NUMBER => Ok(words),
+        magic if magic.swap_bytes() == MAGIC_NUMBER => {
+            Ok(words.into_iter().map(u32::swap_bytes).collect())
+        }
+        magic => Err(InvalidSpirv::Magic(magic)),
+    }
+}
+
+/// Instruction of the SPIR-V module, operands don't include the word with the opcode.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Instruction<'a> {
+    pub(crate) opcode: u32,
+    pub(crate) operands: &'a [u32],
+}
+
+/// Splits the SPIR-V module into instructions. Returns `None` if the module is malformed.
+pub(crate) fn instructions(words: &[u32]) -> Option<Vec<Instruction<'_>>> {
+    if words.len() < HEADER_LEN || words[0] != MAGIC_NUMBER {
+        return None;
+    }
+
+    let mut instructions = Vec::new();
+    let mut offset = HEADER_LEN;
+    while offset < words.len() {
+        let word_count = (words[offset] >> 16) as usize;
+        if word_count == 0 || offset + word_count > words.len() {
+            return None;
+        }
+
+        instructions.push(Instruction {
+            opcode: words[offset] & 0xFFFF,
+            operands: &words[offset + 1..offset + word_count],
+        });
+        offset += word_count;
+    }
+
+    Some(instructions)
+}
+
+/// Scans the instructions of the SPIR-V module and collects all its entry points.
+/// Returns `None` if the module is malformed.
+pub(crate) fn entry_points(words: &[u32]) -> Option<Vec<EntryPoint>> {
+    let mut entry_points = Vec::new();
+    for instruction in instructions(words)? {
+        if instruction.opcode == OP_ENTRY_POINT {
+            if instruction.operands.len() < 3 {
+                return None;
+            }
+
+            let name = literal_string(&instruction.operands[2..])?;
+            // The string is nul-terminated and padded to the whole words.
+            let interface_start = (2 + name.len() / 4 + 1).min(instruction.operands.len());
+            entry_points.push(EntryPoint {
+                execution_model: instruction.operands[0],
+                name,
+                function: instruction.operands[1],
+                interface: instruction.operands[interface_start..].to_vec(),
+            });
+        }
+    }
+
+    Some(entry_points)
+}
+
+/// Decodes the nul-terminated UTF-8 literal string packed into words.
+pub(crate) fn literal_string(words: &[u32]) -> Option<String> {
+    let mut bytes = Vec::new();
+    for word in words {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return String::from_utf8(bytes).ok();
+            }
+            bytes.push(*byte);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SPIR-V header and `OpEntryPoint <execution_model> %<id> "<name>"` for every entry point.
+    fn module(entry_points: &[(u32, &str)]) -> Vec<u32> {
+        let mut words = vec![
+            MAGIC_NUMBER,
+            0x0001_0000,
+            0,
+            2 + entry_points.len() as u32,
+            0,
+        ];
+        for (id, (execution_model, name)) in entry_points.iter().enumerate() {
+            let mut name_bytes = name.as_bytes().to_vec();
+            name_bytes.resize((name.len() / 4 + 1) * 4, 0);
+
+            words.push((((3 + name_bytes.len() / 4) as u32) << 16) | OP_ENTRY_POINT);
+            words.push(*execution_model);
+            words.push(id as u32 + 1);
+            words.extend(
+                name_bytes
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]])),
+            );
+        }
+        words
+    }
+
+    fn bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_ne_bytes()).collect()
+    }
+
+    #[test]
+    fn entry_points_are_mapped_to_stages() {
+        let words = module(&[(0, "VSMain"), (4, "PSMain"), (5365, "main"), (6, "kernel")]);
+
+        let stages: Vec<_> = entry_points(&words)
+            .unwrap()
+            .iter()
+            .map(|entry_point| (entry_point.name.clone(), entry_point.stage()))
+            .collect();
+        assert_eq!(
+            stages,
+            [
+                (String::from("VSMain"), Some(ShaderStageFlags::VERTEX)),
+                (String::from("PSMain"), Some(ShaderStageFlags::FRAGMENT)),
+                (String::from("main"), Some(ShaderStageFlags::MESH_NV)),
+                (String::from("kernel"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn opposite_endian_modules_are_swapped() {
+        let words = module(&[(5, "main")]);
+        let swapped: Vec<u32> = words.iter().map(|word| word.swap_bytes()).collect();
+
+        assert_eq!(words_from_bytes(&bytes(&words)), Ok(words.clone()));
+        assert_eq!(words_from_bytes(&bytes(&swapped)), Ok(words.clone()));
+        assert_eq!(
+            words_from_bytes(&bytes(&words)[1..]),
+            Err(InvalidSpirv::Size(words.len() * 4 - 1))
+        );
+        assert_eq!(
+            words_from_bytes(b"#version 450"),
+            Err(InvalidSpirv::Size(12))
+        );
+    }
+
+    #[test]
+    fn malformed_modules_have_no_entry_points() {
+        let mut truncated = module(&[(0, "main")]);
+        truncated.pop();
+        let mut zero_word_count = module(&[(0, "main")]);
+        zero_word_count.push(0);
+
+        assert_eq!(entry_points(&truncated), None);
+        assert_eq!(entry_points(&zero_word_count), None);
+        assert_eq!(entry_points(&[MAGIC_NUMBER]), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! Configuration of the shader stages that differs between stages, programs and compiled shaders.
+
+use std::{ffi::c_void, path::PathBuf};
+
+use ash::vk::{PipelineShaderStageCreateFlags, ShaderStageFlags, SpecializationInfo};
+
+use crate::{ShaderRecord, SpecializationConstants};
+
+/// Selects the shader stages the `StageConfig` is applied to.
+/// If several configs set the same field, the file config wins over the program one, the program config wins over
+/// the stage one, and of the configs with the same kind of selector the last added wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageSelector {
+    /// Every stage of this type, e.g. `ShaderStageFlags::COMPUTE`.
+    Stage(ShaderStageFlags),
+    /// Every stage of the program with this name, in all its variants.
+    Program(String),
+    /// Every stage of the compiled shader whose path ends with this path, e.g. `sky.frag.spv`,
+    /// or of the `ShaderSource` with this name.
+    File(PathBuf),
+}
+
+impl StageSelector {
+    pub(crate) fn matches(&self, record: &ShaderRecord) -> bool {
+        match self {
+            Self::Stage(stage) => record.stage == *stage,
+            Self::Program(program_name) => record.program_name == *program_name,
+            Self::File(file_path) => record.path.ends_with(file_path),
+        }
+    }
+
+    /// Configs with the higher precedence are applied later.
+    pub(crate) fn precedence(&self) -> usize {
+        match self {
+            Self::Stage(_) => 0,
+            Self::Program(_) => 1,
+            Self::File(_) => 2,
+        }
+    }
+}
+
+/// Fields of the `PipelineShaderStageCreateInfo` that override the defaults of `ShaderStage`
+/// for the stages selected by the `StageSelector`, `None` fields keep the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct StageConfig {
+    pub flags: Option<PipelineShaderStageCreateFlags>,
+    pub p_next: Option<*const c_void>,
+    /// Copied by `ShaderStage::build`, a null pointer disables the default specialization info.
+    spec_info: Option<*const SpecializationInfo>,
+    /// Takes precedence over `spec_info`.
+    pub specialization_constants: Option<SpecializationConstants>,
+}
+
+impl StageConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies `PipelineShaderStageCreateFlags` for the `self.flags` field.
+    pub fn with_flags(mut self, flags: PipelineShaderStageCreateFlags) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// Specifies the pointer to the extension struct for the `self.p_next` field.
+    pub fn with_p_next(mut self, p_next: *const c_void) -> Self {
+        self.p_next = Some(p_next);
+        self
+    }
+
+    /// Specifies `SpecializationInfo` for the `self.spec_info` field.
+    /// Prefer `with_specialization_constants`, which needs no pointers.
+    /// # Safety
+    ///
+    /// Until `ShaderStage::build` is called, not null `spec_info` must point to the valid `SpecializationInfo`,
+    /// whose `p_map_entries` and `p_data` point to `map_entry_count` entries and `data_size` bytes.
+    pub unsafe fn with_spec_info(mut self, spec_info: *const SpecializationInfo) -> Self {
+        self.spec_info = Some(spec_info);
+        self
+    }
+
+    /// Specifies `SpecializationConstants` for the `self.specialization_constants` field.
+    pub fn with_specialization_constants(
+        mut self,
+        specialization_constants: SpecializationConstants,
+    ) -> Self {
+        self.specialization_constants = Some(specialization_constants);
+        self
+    }
+
+    pub(crate) fn spec_info(&self) -> Option<*const SpecializationInfo> {
+        self.spec_info
+    }
+
+    /// Whether the config overrides the default specialization info.
+    pub(crate) fn has_specialization(&self) -> bool {
+        self.spec_info.is_some() || self.specialization_constants.is_some()
+    }
+}
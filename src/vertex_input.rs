@@ -0,0 +1,145 @@
+//! Vertex input state built from the inputs reflected from the vertex stage of a program.
+
+use std::{collections::BTreeMap, ptr};
+
+use ash::vk::{
+    PipelineVertexInputStateCreateFlags, PipelineVertexInputStateCreateInfo, ShaderStageFlags,
+    StructureType, VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate,
+};
+
+use crate::ShaderRecord;
+
+/// Defines how the vertex inputs are assigned to the vertex buffer bindings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VertexBindingAssignment {
+    /// Every input is in the binding 0, interleaved in the order of the locations.
+    #[default]
+    Interleaved,
+    /// Every input is in its own binding, numbered by the location of the input.
+    PerAttribute,
+}
+
+/// Assignment of the vertex inputs to the vertex buffer bindings for `ShaderProgram::vertex_input_descriptions`.
+/// Of the configs of the same location or binding the last added wins.
+#[derive(Debug, Clone, Default)]
+pub struct VertexInputConfig {
+    pub assignment: VertexBindingAssignment,
+    /// Bindings of the inputs by their location, which override the assignment.
+    /// Inputs in the same binding are interleaved in the order of the locations.
+    pub location_bindings: Vec<(u32, u32)>,
+    /// Input rates of the bindings, `VertexInputRate::VERTEX` for the others.
+    pub input_rates: Vec<(u32, VertexInputRate)>,
+}
+
+impl VertexInputConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies `VertexBindingAssignment` for the `self.assignment` field.
+    pub fn with_assignment(mut self, assignment: VertexBindingAssignment) -> Self {
+        self.assignment = assignment;
+        self
+    }
+
+    /// Adds the binding of the input at the location to the `self.location_bindings` field.
+    pub fn with_location_binding(mut self, location: u32, binding: u32) -> Self {
+        self.location_bindings.push((location, binding));
+        self
+    }
+
+    /// Adds `VertexInputRate` of the binding to the `self.input_rates` field.
+    pub fn with_input_rate(mut self, binding: u32, input_rate: VertexInputRate) -> Self {
+        self.input_rates.push((binding, input_rate));
+        self
+    }
+
+    fn binding(&self, location: u32) -> u32 {
+        let assigned = match self.assignment {
+            VertexBindingAssignment::Interleaved => 0,
+            VertexBindingAssignment::PerAttribute => location,
+        };
+
+        self.location_bindings
+            .iter()
+            .rev()
+            .find(|(config_location, _)| *config_location == location)
+            .map_or(assigned, |(_, binding)| *binding)
+    }
+
+    fn input_rate(&self, binding: u32) -> VertexInputRate {
+        self.input_rates
+            .iter()
+            .rev()
+            .find(|(config_binding, _)| *config_binding == binding)
+            .map_or(VertexInputRate::VERTEX, |(_, input_rate)| *input_rate)
+    }
+}
+
+/// Vertex buffer bindings and vertex attributes of the program.
+#[derive(Debug, Clone, Default)]
+pub struct VertexInputDescriptions {
+    /// Bindings sorted by the binding number, the stride is the total size of their attributes.
+    pub bindings: Vec<VertexInputBindingDescription>,
+    /// Attributes sorted by the location, tightly packed in their bindings.
+    pub attributes: Vec<VertexInputAttributeDescription>,
+}
+
+impl VertexInputDescriptions {
+    /// The vertex input state that points to the descriptions, so it can be used only while they live.
+    pub fn create_info(&self) -> PipelineVertexInputStateCreateInfo {
+        PipelineVertexInputStateCreateInfo {
+            s_type: StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: PipelineVertexInputStateCreateFlags::empty(),
+            vertex_binding_description_count: self.bindings.len() as u32,
+            p_vertex_binding_descriptions: self.bindings.as_ptr(),
+            vertex_attribute_description_count: self.attributes.len() as u32,
+            p_vertex_attribute_descriptions: self.attributes.as_ptr(),
+        }
+    }
+}
+
+/// Builds the descriptions from the inputs of the vertex stage of the program, empty without the vertex stage.
+pub(crate) fn vertex_input_descriptions(
+    records: &[ShaderRecord],
+    config: &VertexInputConfig,
+) -> VertexInputDescriptions {
+    let vertex_inputs = match records
+        .iter()
+        .find(|record| record.stage == ShaderStageFlags::VERTEX)
+    {
+        Some(record) => &record.reflection.vertex_inputs,
+        None => return VertexInputDescriptions::default(),
+    };
+
+    let mut strides = BTreeMap::new();
+    let mut attributes = Vec::new();
+    for vertex_input in vertex_inputs {
+        let binding = config.binding(vertex_input.location);
+        let stride = strides.entry(binding).or_insert(0);
+        for index in 0..vertex_input.attribute_count {
+            attributes.push(VertexInputAttributeDescription {
+                location: vertex_input.location + index * vertex_input.locations_per_attribute,
+                binding,
+                format: vertex_input.format,
+                offset: *stride,
+            });
+            *stride += vertex_input.size;
+        }
+    }
+
+    let bindings = strides
+        .into_iter()
+        .map(|(binding, stride)| VertexInputBindingDescription {
+            binding,
+            stride,
+            input_rate: config.input_rate(binding),
+        })
+        .collect();
+
+    VertexInputDescriptions {
+        bindings,
+        attributes,
+    }
+}
@@ -0,0 +1,524 @@
+//! SPIR-V modules for the tests, built only from the instructions the library reflects.
+
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub const VERTEX: u32 = 0;
+pub const FRAGMENT: u32 = 4;
+pub const GL_COMPUTE: u32 = 5;
+
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_TYPE_VOID: u32 = 19;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_FUNCTION_CALL: u32 = 57;
+const OP_VARIABLE: u32 = 59;
+const OP_LOAD: u32 = 61;
+const OP_DECORATE: u32 = 71;
+const OP_LABEL: u32 = 248;
+const OP_RETURN: u32 = 253;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_TYPE_ACCELERATION_STRUCTURE_KHR: u32 = 5341;
+
+const DECORATION_SPEC_ID: u32 = 1;
+pub const DECORATION_BLOCK: u32 = 2;
+pub const DECORATION_BUFFER_BLOCK: u32 = 3;
+pub const DECORATION_ROW_MAJOR: u32 = 4;
+pub const DECORATION_ARRAY_STRIDE: u32 = 6;
+pub const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+pub const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_INPUT: u32 = 1;
+pub const STORAGE_CLASS_UNIFORM: u32 = 2;
+pub const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+pub const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+pub const DIM_2D: u32 = 1;
+pub const DIM_BUFFER: u32 = 5;
+pub const DIM_SUBPASS_DATA: u32 = 6;
+
+/// Value of the specialization constant declared in the module.
+#[derive(Debug, Clone, Copy)]
+pub enum Constant {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    F64(f64),
+}
+
+/// The SPIR-V module, instructions are grouped in the order of the logical layout.
+#[derive(Default)]
+pub struct Module {
+    next_id: u32,
+    /// Operands of every `OpEntryPoint`, the interface is extended by the inputs declared after it.
+    entry_points: Vec<Vec<u32>>,
+    names: Vec<u32>,
+    decorations: Vec<u32>,
+    globals: Vec<u32>,
+    functions: Vec<u32>,
+}
+
+impl Module {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    /// `OpEntryPoint <execution_model> %<id> "<name>"`.
+    pub fn entry_point(mut self, execution_model: u32, name: &str) -> Self {
+        let id = self.id();
+        let mut operands = vec![execution_model, id];
+        operands.extend(literal_string(name));
+        self.entry_points.push(operands);
+        self
+    }
+
+    /// `OpSpecConstant*` decorated with `SpecId` and named with `OpName`.
+    pub fn spec_constant(mut self, spec_id: u32, name: &str, value: Constant) -> Self {
+        let type_id = self.id();
+        let id = self.id();
+        let (type_instruction, opcode, value_words) = match value {
+            Constant::Bool(value) => (
+                (OP_TYPE_BOOL, vec![type_id]),
+                if value {
+                    OP_SPEC_CONSTANT_TRUE
+                } else {
+                    OP_SPEC_CONSTANT_FALSE
+                },
+                vec![],
+            ),
+            Constant::I32(value) => (
+                (OP_TYPE_INT, vec![type_id, 32, 1]),
+                OP_SPEC_CONSTANT,
+                vec![value as u32],
+            ),
+            Constant::U32(value) => (
+                (OP_TYPE_INT, vec![type_id, 32, 0]),
+                OP_SPEC_CONSTANT,
+                vec![value],
+            ),
+            Constant::F32(value) => (
+                (OP_TYPE_FLOAT, vec![type_id, 32]),
+                OP_SPEC_CONSTANT,
+                vec![value.to_bits()],
+            ),
+            Constant::F64(value) => {
+                let bits = value.to_bits();
+                (
+                    (OP_TYPE_FLOAT, vec![type_id, 64]),
+                    OP_SPEC_CONSTANT,
+                    vec![bits as u32, (bits >> 32) as u32],
+                )
+            }
+        };
+
+        self.name(id, name);
+        self.decorate(id, DECORATION_SPEC_ID, &[spec_id]);
+        push(&mut self.globals, type_instruction.0, &type_instruction.1);
+        let mut operands = vec![type_id, id];
+        operands.extend(value_words);
+        push(&mut self.globals, opcode, &operands);
+        self
+    }
+
+    pub fn type_int(&mut self, width: u32, signed: bool) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_INT, &[id, width, signed as u32]);
+        id
+    }
+
+    pub fn type_float(&mut self, width: u32) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_FLOAT, &[id, width]);
+        id
+    }
+
+    pub fn type_vector(&mut self, component_type: u32, count: u32) -> u32 {
+        let id = self.id();
+        push(
+            &mut self.globals,
+            OP_TYPE_VECTOR,
+            &[id, component_type, count],
+        );
+        id
+    }
+
+    pub fn type_matrix(&mut self, column_type: u32, count: u32) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_MATRIX, &[id, column_type, count]);
+        id
+    }
+
+    /// `Sampled` is 1 for the images used with a sampler and 2 for the storage ones.
+    pub fn type_image(&mut self, dim: u32, sampled: u32) -> u32 {
+        let sampled_type = self.type_float(32);
+        let id = self.id();
+        push(
+            &mut self.globals,
+            OP_TYPE_IMAGE,
+            &[id, sampled_type, dim, 0, 0, 0, sampled, 0],
+        );
+        id
+    }
+
+    pub fn type_sampler(&mut self) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_SAMPLER, &[id]);
+        id
+    }
+
+    pub fn type_sampled_image(&mut self, image_type: u32) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_SAMPLED_IMAGE, &[id, image_type]);
+        id
+    }
+
+    pub fn type_acceleration_structure(&mut self) -> u32 {
+        let id = self.id();
+        push(&mut self.globals, OP_TYPE_ACCELERATION_STRUCTURE_KHR, &[id]);
+        id
+    }
+
+    /// The array of `length` elements, the runtime array if `length` is `None`.
+    pub fn type_array(&mut self, element_type: u32, length: Option<u32>) -> u32 {
+        match length {
+            Some(length) => {
+                let length_type = self.id();
+                let length_id = self.id();
+                push(&mut self.globals, OP_TYPE_INT, &[length_type, 32, 0]);
+                push(
+                    &mut self.globals,
+                    OP_CONSTANT,
+                    &[length_type, length_id, length],
+                );
+                let id = self.id();
+                push(
+                    &mut self.globals,
+                    OP_TYPE_ARRAY,
+                    &[id, element_type, length_id],
+                );
+                id
+            }
+            None => {
+                let id = self.id();
+                push(
+                    &mut self.globals,
+                    OP_TYPE_RUNTIME_ARRAY,
+                    &[id, element_type],
+                );
+                id
+            }
+        }
+    }
+
+    pub fn type_struct(&mut self, member_types: &[u32]) -> u32 {
+        let id = self.id();
+        let mut operands = vec![id];
+        operands.extend(member_types);
+        push(&mut self.globals, OP_TYPE_STRUCT, &operands);
+        id
+    }
+
+    /// The struct of one `float` decorated with `Block` or `BufferBlock`.
+    pub fn type_block(&mut self, decoration: u32) -> u32 {
+        let member_type = self.type_float(32);
+        let id = self.type_struct(&[member_type]);
+        self.decorate(id, decoration, &[]);
+        id
+    }
+
+    /// `OpVariable` of the pointer to `pointee_type` in the storage class.
+    pub fn variable(&mut self, storage_class: u32, pointee_type: u32) -> u32 {
+        let pointer_type = self.id();
+        push(
+            &mut self.globals,
+            OP_TYPE_POINTER,
+            &[pointer_type, storage_class, pointee_type],
+        );
+        let id = self.id();
+        push(
+            &mut self.globals,
+            OP_VARIABLE,
+            &[pointer_type, id, storage_class],
+        );
+        id
+    }
+
+    /// The block in the `PushConstant` storage class with the members of the type at the offset.
+    pub fn push_constant_block(&mut self, name: &str, members: &[(&str, u32, u32)]) -> u32 {
+        self.push_constant_variable(name, members).0
+    }
+
+    /// Like `push_constant_block`, but returns the ids of both the block and its variable.
+    pub fn push_constant_variable(
+        &mut self,
+        name: &str,
+        members: &[(&str, u32, u32)],
+    ) -> (u32, u32) {
+        let member_types: Vec<_> = members
+            .iter()
+            .map(|(_, member_type, _)| *member_type)
+            .collect();
+        let block = self.type_struct(&member_types);
+        self.name(block, name);
+        self.decorate(block, DECORATION_BLOCK, &[]);
+        for (index, (member_name, _, offset)) in members.iter().enumerate() {
+            self.member_name(block, index as u32, member_name);
+            self.member_decorate(block, index as u32, DECORATION_OFFSET, &[*offset]);
+        }
+        let variable = self.variable(STORAGE_CLASS_PUSH_CONSTANT, block);
+        (block, variable)
+    }
+
+    /// `OpTypeArray` with the length of the constant `length_id`, e.g. a specialization constant.
+    pub fn type_array_sized_by(&mut self, element_type: u32, length_id: u32) -> u32 {
+        let id = self.id();
+        self.declare_array(id, element_type, length_id);
+        id
+    }
+
+    /// `OpTypeArray` with the given result id, so the element type may be the array itself.
+    pub fn declare_array(&mut self, id: u32, element_type: u32, length_id: u32) {
+        push(
+            &mut self.globals,
+            OP_TYPE_ARRAY,
+            &[id, element_type, length_id],
+        );
+    }
+
+    /// The `uint` specialization constant decorated with `SpecId`.
+    pub fn uint_spec_constant(&mut self, spec_id: u32, value: u32) -> u32 {
+        let type_id = self.type_int(32, false);
+        let id = self.id();
+        self.decorate(id, DECORATION_SPEC_ID, &[spec_id]);
+        push(&mut self.globals, OP_SPEC_CONSTANT, &[type_id, id, value]);
+        id
+    }
+
+    /// The variable decorated with `DescriptorSet` and `Binding`.
+    pub fn descriptor(
+        &mut self,
+        set: u32,
+        binding: u32,
+        name: &str,
+        storage_class: u32,
+        pointee_type: u32,
+    ) -> u32 {
+        let id = self.variable(storage_class, pointee_type);
+        self.name(id, name);
+        self.decorate(id, DECORATION_DESCRIPTOR_SET, &[set]);
+        self.decorate(id, DECORATION_BINDING, &[binding]);
+        id
+    }
+
+    /// The `Input` variable decorated with `Location` in the interface of the last entry point.
+    pub fn input(&mut self, location: u32, name: &str, pointee_type: u32) -> u32 {
+        let id = self.interface_input(pointee_type);
+        self.name(id, name);
+        self.decorate(id, DECORATION_LOCATION, &[location]);
+        id
+    }
+
+    /// The `Input` variable decorated with `BuiltIn` in the interface of the last entry point.
+    pub fn built_in_input(&mut self, built_in: u32, pointee_type: u32) -> u32 {
+        let id = self.interface_input(pointee_type);
+        self.decorate(id, DECORATION_BUILT_IN, &[built_in]);
+        id
+    }
+
+    fn interface_input(&mut self, pointee_type: u32) -> u32 {
+        let id = self.variable(STORAGE_CLASS_INPUT, pointee_type);
+        self.entry_points
+            .last_mut()
+            .expect("Inputs are declared after the entry point")
+            .push(id);
+        id
+    }
+
+    /// Id of the function of the last entry point.
+    pub fn entry_point_function(&self) -> u32 {
+        self.entry_points
+            .last()
+            .expect("The entry point is declared before its function")[1]
+    }
+
+    /// The function that loads the variables and calls the functions, types of the results aren't checked.
+    pub fn function(&mut self, id: u32, variables: &[u32], callees: &[u32]) {
+        let void_type = self.id();
+        push(&mut self.globals, OP_TYPE_VOID, &[void_type]);
+        let label = self.id();
+        push(
+            &mut self.functions,
+            OP_FUNCTION,
+            &[void_type, id, 0, void_type],
+        );
+        push(&mut self.functions, OP_LABEL, &[label]);
+        for variable in variables {
+            let result = self.id();
+            push(
+                &mut self.functions,
+                OP_LOAD,
+                &[void_type, result, *variable],
+            );
+        }
+        for callee in callees {
+            let result = self.id();
+            push(
+                &mut self.functions,
+                OP_FUNCTION_CALL,
+                &[void_type, result, *callee],
+            );
+        }
+        push(&mut self.functions, OP_RETURN, &[]);
+        push(&mut self.functions, OP_FUNCTION_END, &[]);
+    }
+
+    pub fn decorate(&mut self, id: u32, decoration: u32, literals: &[u32]) {
+        let mut operands = vec![id, decoration];
+        operands.extend(literals);
+        push(&mut self.decorations, OP_DECORATE, &operands);
+    }
+
+    pub fn member_decorate(&mut self, id: u32, member: u32, decoration: u32, literals: &[u32]) {
+        let mut operands = vec![id, member, decoration];
+        operands.extend(literals);
+        push(&mut self.decorations, OP_MEMBER_DECORATE, &operands);
+    }
+
+    pub fn member_name(&mut self, id: u32, member: u32, name: &str) {
+        let mut operands = vec![id, member];
+        operands.extend(literal_string(name));
+        push(&mut self.names, OP_MEMBER_NAME, &operands);
+    }
+
+    pub fn name(&mut self, id: u32, name: &str) {
+        let mut operands = vec![id];
+        operands.extend(literal_string(name));
+        push(&mut self.names, OP_NAME, &operands);
+    }
+
+    pub fn words(&self) -> Vec<u32> {
+        let mut words = vec![0x0723_0203, 0x0001_0000, 0, self.next_id, 0];
+        for operands in &self.entry_points {
+            push(&mut words, OP_ENTRY_POINT, operands);
+        }
+        words.extend(&self.names);
+        words.extend(&self.decorations);
+        words.extend(&self.globals);
+        words.extend(&self.functions);
+        words
+    }
+}
+
+/// SPIR-V header and `OpEntryPoint <execution_model> %<id> "<name>"` for every entry point.
+pub fn spirv(entry_points: &[(u32, &str)]) -> Vec<u32> {
+    entry_points
+        .iter()
+        .fold(Module::new(), |module, (execution_model, name)| {
+            module.entry_point(*execution_model, name)
+        })
+        .words()
+}
+
+fn push(words: &mut Vec<u32>, opcode: u32, operands: &[u32]) {
+    words.push((((operands.len() + 1) as u32) << 16) | opcode);
+    words.extend(operands);
+}
+
+/// Packs the nul-terminated UTF-8 string into words.
+pub fn literal_string(string: &str) -> Vec<u32> {
+    let mut bytes = string.as_bytes().to_vec();
+    bytes.resize((string.len() / 4 + 1) * 4, 0);
+    bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect()
+}
+
+/// The directory in the system temp directory, removed with its content on drop.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "ash_shader_creator_{}_{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the file at the path relative to the directory, creating its parent directories.
+    pub fn write(&self, relative_path: &str, bytes: &[u8]) -> PathBuf {
+        let path = self.path.join(relative_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// Writes the SPIR-V words as the compiled shader file.
+    pub fn write_words(&self, relative_path: &str, words: &[u32]) -> PathBuf {
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        self.write(relative_path, &bytes)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Reads the compiled shader file back as SPIR-V words.
+pub fn words_of_file(path: &Path) -> Vec<u32> {
+    fs::read(path)
+        .unwrap()
+        .chunks_exact(4)
+        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+        .collect()
+}
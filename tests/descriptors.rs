@@ -0,0 +1,371 @@
+mod common;
+
+use ash::vk::{DescriptorType, ShaderStageFlags};
+use ash_shader_creator::{
+    DescriptorBindingInfo, MockDevice, ShaderCreatorError, ShaderSource, ShaderStage,
+};
+use common::{
+    Module, DECORATION_BLOCK, DECORATION_BUFFER_BLOCK, DIM_2D, DIM_BUFFER, DIM_SUBPASS_DATA,
+    FRAGMENT, STORAGE_CLASS_STORAGE_BUFFER, STORAGE_CLASS_UNIFORM, STORAGE_CLASS_UNIFORM_CONSTANT,
+    VERTEX,
+};
+
+/// Both stages use the camera block and the textures, the rest is used only by one of them.
+fn scene_vertex() -> Vec<u32> {
+    let mut module = Module::new().entry_point(VERTEX, "main");
+    let camera = module.type_block(DECORATION_BLOCK);
+    module.descriptor(0, 0, "camera", STORAGE_CLASS_UNIFORM, camera);
+    let instances = module.type_block(DECORATION_BUFFER_BLOCK);
+    module.descriptor(0, 1, "", STORAGE_CLASS_UNIFORM, instances);
+    module.name(instances, "Instances");
+    let image = module.type_image(DIM_2D, 1);
+    let sampled_image = module.type_sampled_image(image);
+    let textures = module.type_array(sampled_image, Some(4));
+    module.descriptor(1, 0, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+    module.words()
+}
+
+fn scene_fragment() -> Vec<u32> {
+    let mut module = Module::new().entry_point(FRAGMENT, "main");
+    let camera = module.type_block(DECORATION_BLOCK);
+    module.descriptor(0, 0, "camera", STORAGE_CLASS_UNIFORM, camera);
+    let image = module.type_image(DIM_2D, 1);
+    let sampled_image = module.type_sampled_image(image);
+    let rows = module.type_array(sampled_image, Some(2));
+    let textures = module.type_array(rows, Some(4));
+    module.descriptor(1, 0, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+
+    let lights = module.type_block(DECORATION_BLOCK);
+    module.descriptor(1, 3, "lights", STORAGE_CLASS_STORAGE_BUFFER, lights);
+    let output = module.type_image(DIM_2D, 2);
+    module.descriptor(1, 1, "output", STORAGE_CLASS_UNIFORM_CONSTANT, output);
+    let sampler = module.type_sampler();
+    module.descriptor(
+        1,
+        2,
+        "shadow_sampler",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        sampler,
+    );
+    let uniform_texels = module.type_image(DIM_BUFFER, 1);
+    module.descriptor(
+        2,
+        0,
+        "weights",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        uniform_texels,
+    );
+    let storage_texels = module.type_image(DIM_BUFFER, 2);
+    module.descriptor(
+        2,
+        1,
+        "histogram",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        storage_texels,
+    );
+    let subpass = module.type_image(DIM_SUBPASS_DATA, 0);
+    module.descriptor(2, 2, "albedo", STORAGE_CLASS_UNIFORM_CONSTANT, subpass);
+    let acceleration_structure = module.type_acceleration_structure();
+    module.descriptor(
+        2,
+        3,
+        "tlas",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        acceleration_structure,
+    );
+    let sampled = module.type_image(DIM_2D, 1);
+    let bindless = module.type_array(sampled, None);
+    module.descriptor(3, 0, "bindless", STORAGE_CLASS_UNIFORM_CONSTANT, bindless);
+    module.words()
+}
+
+#[test]
+fn descriptor_bindings_are_reflected() {
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words(
+            "scene.vert.spv",
+            None,
+            &scene_vertex(),
+        )],
+    )
+    .load_records()
+    .unwrap();
+
+    let binding =
+        |set, binding, name: &str, descriptor_type, descriptor_count| DescriptorBindingInfo {
+            set,
+            binding,
+            name: Some(String::from(name)),
+            descriptor_type,
+            descriptor_count,
+        };
+    assert_eq!(
+        records[0].reflection.descriptor_bindings,
+        [
+            binding(0, 0, "camera", DescriptorType::UNIFORM_BUFFER, 1),
+            binding(0, 1, "Instances", DescriptorType::STORAGE_BUFFER, 1),
+            binding(1, 0, "textures", DescriptorType::COMBINED_IMAGE_SAMPLER, 4),
+        ]
+    );
+}
+
+#[test]
+fn descriptor_set_bindings_are_merged_across_stages() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_words("scene.vert.spv", None, &scene_vertex()),
+            ShaderSource::from_words("scene.frag.spv", None, &scene_fragment()),
+        ],
+    )
+    .build()
+    .unwrap();
+
+    let sets = shader_set
+        .program("scene")
+        .unwrap()
+        .descriptor_set_bindings()
+        .unwrap();
+    let sets: Vec<_> = sets
+        .iter()
+        .map(|(set, bindings)| {
+            let bindings: Vec<_> = bindings
+                .iter()
+                .map(|binding| {
+                    assert!(binding.p_immutable_samplers.is_null());
+                    (
+                        binding.binding,
+                        binding.descriptor_type,
+                        binding.descriptor_count,
+                        binding.stage_flags,
+                    )
+                })
+                .collect();
+            (*set, bindings)
+        })
+        .collect();
+
+    let all = ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT;
+    let fragment = ShaderStageFlags::FRAGMENT;
+    assert_eq!(
+        sets,
+        [
+            (
+                0,
+                vec![
+                    (0, DescriptorType::UNIFORM_BUFFER, 1, all),
+                    (
+                        1,
+                        DescriptorType::STORAGE_BUFFER,
+                        1,
+                        ShaderStageFlags::VERTEX
+                    ),
+                ]
+            ),
+            (
+                1,
+                vec![
+                    (0, DescriptorType::COMBINED_IMAGE_SAMPLER, 8, all),
+                    (1, DescriptorType::STORAGE_IMAGE, 1, fragment),
+                    (2, DescriptorType::SAMPLER, 1, fragment),
+                    (3, DescriptorType::STORAGE_BUFFER, 1, fragment),
+                ]
+            ),
+            (
+                2,
+                vec![
+                    (0, DescriptorType::UNIFORM_TEXEL_BUFFER, 1, fragment),
+                    (1, DescriptorType::STORAGE_TEXEL_BUFFER, 1, fragment),
+                    (2, DescriptorType::INPUT_ATTACHMENT, 1, fragment),
+                    (3, DescriptorType::ACCELERATION_STRUCTURE_KHR, 1, fragment),
+                ]
+            ),
+            (3, vec![(0, DescriptorType::SAMPLED_IMAGE, 0, fragment)]),
+        ]
+    );
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn mismatched_descriptor_types_are_reported() {
+    let mut fragment = Module::new().entry_point(FRAGMENT, "main");
+    let camera = fragment.type_block(DECORATION_BLOCK);
+    fragment.descriptor(0, 0, "camera", STORAGE_CLASS_STORAGE_BUFFER, camera);
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_words("scene.vert.spv", None, &scene_vertex()),
+            ShaderSource::from_words("scene.frag.spv", None, &fragment.words()),
+        ],
+    )
+    .build()
+    .unwrap();
+
+    let error = shader_set
+        .program("scene")
+        .unwrap()
+        .descriptor_set_bindings()
+        .expect_err("the camera is a uniform buffer in the vertex stage");
+    match error {
+        ShaderCreatorError::DescriptorTypeMismatch {
+            path,
+            stage,
+            set,
+            binding,
+            expected,
+            found,
+        } => {
+            assert_eq!(path.to_str(), Some("scene.frag.spv"));
+            assert_eq!(stage, ShaderStageFlags::FRAGMENT);
+            assert_eq!((set, binding), (0, 0));
+            assert_eq!(expected, DescriptorType::UNIFORM_BUFFER);
+            assert_eq!(found, DescriptorType::STORAGE_BUFFER);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn arrays_sized_by_specialization_constants_use_the_default_value() {
+    let mut module = Module::new().entry_point(FRAGMENT, "main");
+    let image = module.type_image(DIM_2D, 1);
+    let sampled_image = module.type_sampled_image(image);
+    let texture_count = module.uint_spec_constant(0, 16);
+    let textures = module.type_array_sized_by(sampled_image, texture_count);
+    module.descriptor(0, 0, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+    // The result of `OpSpecConstantOp` isn't reflected, so the length can't be resolved.
+    let shadow_map_count = module.id();
+    let shadow_maps = module.type_array_sized_by(sampled_image, shadow_map_count);
+    let cascades = module.type_array(shadow_maps, Some(4));
+    module.descriptor(
+        0,
+        1,
+        "shadow_maps",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        cascades,
+    );
+
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words(
+            "scene.frag.spv",
+            None,
+            &module.words(),
+        )],
+    )
+    .load_records()
+    .unwrap();
+
+    let counts: Vec<_> = records[0]
+        .reflection
+        .descriptor_bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.descriptor_count))
+        .collect();
+    assert_eq!(counts, [(0, 16), (1, 0)]);
+}
+
+#[test]
+fn entry_points_of_one_module_get_the_stage_flags_of_their_own_resources() {
+    let module = Module::new().entry_point(VERTEX, "VSMain");
+    let vertex_main = module.entry_point_function();
+    let mut module = module.entry_point(FRAGMENT, "PSMain");
+    let fragment_main = module.entry_point_function();
+    let camera = module.type_block(DECORATION_BLOCK);
+    let camera = module.descriptor(0, 0, "camera", STORAGE_CLASS_UNIFORM, camera);
+    let image = module.type_image(DIM_2D, 1);
+    let sampled_image = module.type_sampled_image(image);
+    let albedo = module.descriptor(
+        0,
+        1,
+        "albedo",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        sampled_image,
+    );
+    let unused = module.type_block(DECORATION_BLOCK);
+    module.descriptor(0, 2, "unused", STORAGE_CLASS_UNIFORM, unused);
+    let shade = module.id();
+    module.function(vertex_main, &[camera], &[]);
+    module.function(fragment_main, &[], &[shade]);
+    module.function(shade, &[camera, albedo], &[]);
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words("scene.spv", None, &module.words())],
+    )
+    .build()
+    .unwrap();
+
+    let bindings = shader_set
+        .program("scene")
+        .unwrap()
+        .descriptor_set_bindings()
+        .unwrap();
+    let stage_flags: Vec<_> = bindings[&0]
+        .iter()
+        .map(|binding| (binding.binding, binding.stage_flags))
+        .collect();
+    assert_eq!(
+        stage_flags,
+        [
+            (0, ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT),
+            (1, ShaderStageFlags::FRAGMENT),
+        ]
+    );
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn cyclic_and_overflowing_array_types_are_malformed() {
+    let load = |words: &[u32]| {
+        let device = MockDevice::new();
+        let error = ShaderStage::from_sources(
+            &device,
+            vec![ShaderSource::from_words("scene.frag.spv", None, words)],
+        )
+        .load_records()
+        .expect_err("the module is malformed");
+        assert!(device.created_modules().is_empty());
+        error
+    };
+
+    // `OpTypeArray %5 %5 %3`, the array of itself.
+    let mut cyclic = Module::new().entry_point(FRAGMENT, "main");
+    let length = cyclic.uint_spec_constant(0, 2);
+    let textures = cyclic.id();
+    cyclic.declare_array(textures, textures, length);
+    cyclic.descriptor(0, 0, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+
+    let mut overflowing = Module::new().entry_point(FRAGMENT, "main");
+    let sampler = overflowing.type_sampler();
+    let rows = overflowing.type_array(sampler, Some(1 << 16));
+    let samplers = overflowing.type_array(rows, Some(1 << 16));
+    overflowing.descriptor(0, 0, "samplers", STORAGE_CLASS_UNIFORM_CONSTANT, samplers);
+
+    let mut deep = Module::new().entry_point(FRAGMENT, "main");
+    let mut textures = deep.type_sampler();
+    for _ in 0..1000 {
+        textures = deep.type_array(textures, Some(1));
+    }
+    deep.descriptor(0, 0, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+
+    for module in [cyclic, overflowing, deep] {
+        match load(&module.words()) {
+            ShaderCreatorError::MalformedSpirv { path } => {
+                assert_eq!(path.to_str(), Some("scene.frag.spv"))
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
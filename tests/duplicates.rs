@@ -0,0 +1,165 @@
+mod common;
+
+use std::path::Path;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    DuplicatePolicy, ErrorPolicy, MockDevice, ShaderCreatorError, ShaderRecord, ShaderStage,
+};
+use common::{spirv, TempDir, FRAGMENT, VERTEX};
+
+/// Stages of the program as the stage, the entry point and the path of the compiled shader.
+fn stages(records: &[ShaderRecord]) -> Vec<(ShaderStageFlags, &str, &Path)> {
+    records
+        .iter()
+        .map(|record| {
+            (
+                record.stage,
+                record.entry_point.as_str(),
+                record.path.as_path(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn duplicates_are_errors_by_default() {
+    let shared = TempDir::new();
+    let feature = TempDir::new();
+    let shared_path = shared.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    let feature_path = feature.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "feature")]));
+    shared.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+
+    let device = MockDevice::new();
+    let error = ShaderStage::from_dir_paths(&device, &[shared.path(), feature.path()])
+        .build()
+        .err()
+        .expect("the duplicate must fail the build");
+    match error {
+        ShaderCreatorError::DuplicateShader {
+            program_name,
+            variant,
+            stage,
+            first_path,
+            second_path,
+        } => {
+            assert_eq!(program_name, "sky");
+            assert_eq!(variant, None);
+            assert_eq!(stage, ShaderStageFlags::FRAGMENT);
+            assert_eq!(first_path, shared_path);
+            assert_eq!(second_path, feature_path);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert!(device.created_modules().is_empty());
+
+    let shader_set = ShaderStage::from_dir_paths(&device, &[shared.path(), feature.path()])
+        .with_error_policy(ErrorPolicy::Collect)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stages(shader_set.records()),
+        [
+            (
+                ShaderStageFlags::VERTEX,
+                "main",
+                shared.path().join("sky.vert.spv").as_path()
+            ),
+            (ShaderStageFlags::FRAGMENT, "main", shared_path.as_path()),
+        ]
+    );
+    assert_eq!(shader_set.failures().len(), 1);
+    assert!(matches!(
+        shader_set.failures()[0].error,
+        ShaderCreatorError::DuplicateShader { .. }
+    ));
+}
+
+#[test]
+fn first_or_last_duplicate_wins() {
+    let shared = TempDir::new();
+    let feature = TempDir::new();
+    let shared_path = shared.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    let feature_path = feature.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "feature")]));
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_dir_paths(&device, &[shared.path(), feature.path()])
+        .with_duplicate_policy(DuplicatePolicy::FirstWins)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stages(shader_set.records()),
+        [(ShaderStageFlags::FRAGMENT, "main", shared_path.as_path())]
+    );
+
+    let shader_set = ShaderStage::from_dir_paths(&device, &[shared.path(), feature.path()])
+        .with_duplicate_policy(DuplicatePolicy::LastWins)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stages(shader_set.records()),
+        [(
+            ShaderStageFlags::FRAGMENT,
+            "feature",
+            feature_path.as_path()
+        )]
+    );
+    // The losing duplicates are never created.
+    assert_eq!(device.created_modules().len(), 2);
+}
+
+#[test]
+fn last_duplicate_replaces_only_its_stage_of_the_module_with_several_entry_points() {
+    let shared = TempDir::new();
+    let feature = TempDir::new();
+    let module_path = shared.write_words(
+        "sky.spv",
+        &spirv(&[(VERTEX, "sky_vertex"), (FRAGMENT, "sky_fragment")]),
+    );
+    let feature_path = feature.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "feature")]));
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_dir_paths(&device, &[shared.path(), feature.path()])
+        .with_duplicate_policy(DuplicatePolicy::LastWins)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stages(shader_set.records()),
+        [
+            (
+                ShaderStageFlags::VERTEX,
+                "sky_vertex",
+                module_path.as_path()
+            ),
+            (
+                ShaderStageFlags::FRAGMENT,
+                "feature",
+                feature_path.as_path()
+            ),
+        ]
+    );
+    assert_eq!(device.created_modules().len(), 2);
+
+    // The module whose every stage is replaced isn't created at all.
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_dir_paths(&device, &[feature.path(), shared.path()])
+        .with_duplicate_policy(DuplicatePolicy::LastWins)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stages(shader_set.records()),
+        [
+            (
+                ShaderStageFlags::VERTEX,
+                "sky_vertex",
+                module_path.as_path()
+            ),
+            (
+                ShaderStageFlags::FRAGMENT,
+                "sky_fragment",
+                module_path.as_path()
+            ),
+        ]
+    );
+    assert_eq!(device.created_modules().len(), 1);
+}
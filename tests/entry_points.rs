@@ -0,0 +1,199 @@
+mod common;
+
+use std::{ffi::CStr, path::Path};
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    DuplicatePolicy, ErrorPolicy, MockDevice, ShaderCreatorError, ShaderSet, ShaderSource,
+    ShaderStage, StageDetection,
+};
+use common::{spirv, FRAGMENT, VERTEX};
+
+fn sources() -> Vec<ShaderSource> {
+    vec![
+        ShaderSource::from_words("sky.vert.spv", None, &spirv(&[(VERTEX, "VSMain")])),
+        ShaderSource::from_words("sky.frag.spv", None, &spirv(&[(FRAGMENT, "PSMain")])),
+        ShaderSource::from_words("sea.frag.spv", None, &spirv(&[(FRAGMENT, "main")])),
+    ]
+}
+
+fn entry_point_names(shader_set: &ShaderSet<MockDevice>) -> Vec<(String, String)> {
+    shader_set
+        .records()
+        .iter()
+        .zip(shader_set.stages())
+        .map(|(record, stage)| {
+            let p_name = unsafe { CStr::from_ptr(stage.p_name) };
+            assert_eq!(p_name.to_str(), Ok(record.entry_point.as_str()));
+            (
+                record.path.display().to_string(),
+                record.entry_point.clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn entry_points_are_read_from_spirv() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        entry_point_names(&shader_set),
+        [
+            ("sea.frag.spv".to_owned(), "main".to_owned()),
+            ("sky.vert.spv".to_owned(), "VSMain".to_owned()),
+            ("sky.frag.spv".to_owned(), "PSMain".to_owned()),
+        ]
+    );
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn entry_point_overrides_take_precedence() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .with_main_function_name("Main")
+        .with_stage_entry_point(ShaderStageFlags::FRAGMENT, "FragmentMain")
+        .with_file_entry_point(Path::new("sea.frag.spv"), "SeaMain")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        entry_point_names(&shader_set),
+        [
+            ("sea.frag.spv".to_owned(), "SeaMain".to_owned()),
+            ("sky.vert.spv".to_owned(), "Main".to_owned()),
+            ("sky.frag.spv".to_owned(), "FragmentMain".to_owned()),
+        ]
+    );
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn module_with_several_entry_points_is_expanded_into_stages() {
+    let device = MockDevice::new();
+    let code = spirv(&[(VERTEX, "VSMain"), (FRAGMENT, "PSMain")]);
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words("sky.spv", None, &code)],
+    )
+    .build()
+    .unwrap();
+
+    let sky = shader_set.program("sky").unwrap();
+    let stages: Vec<_> = sky
+        .records()
+        .iter()
+        .map(|record| (record.stage, record.entry_point.as_str(), record.module))
+        .collect();
+    let module = device.created_modules()[0].module;
+    assert_eq!(
+        stages,
+        [
+            (ShaderStageFlags::VERTEX, "VSMain", module),
+            (ShaderStageFlags::FRAGMENT, "PSMain", module),
+        ]
+    );
+    assert_eq!(device.created_modules().len(), 1);
+
+    shader_set.destroy(&device);
+    assert_eq!(device.destroyed_modules(), [module]);
+}
+
+#[test]
+fn entry_points_of_the_same_stage_follow_the_duplicate_policy() {
+    let device = MockDevice::new();
+    let code = spirv(&[
+        (VERTEX, "VSMain"),
+        (FRAGMENT, "PSMain"),
+        (FRAGMENT, "OtherPSMain"),
+    ]);
+    let stage = |duplicate_policy, error_policy| {
+        ShaderStage::from_sources(
+            &device,
+            vec![ShaderSource::from_words("sky.spv", None, &code)],
+        )
+        .with_duplicate_policy(duplicate_policy)
+        .with_error_policy(error_policy)
+    };
+    let is_duplicate_entry_point = |error: &ShaderCreatorError| {
+        matches!(
+            error,
+            ShaderCreatorError::DuplicateEntryPoint {
+                path,
+                stage: ShaderStageFlags::FRAGMENT,
+                first_entry_point,
+                second_entry_point,
+            } if path == Path::new("sky.spv")
+                && first_entry_point == "PSMain"
+                && second_entry_point == "OtherPSMain"
+        )
+    };
+
+    let plan = stage(DuplicatePolicy::Error, ErrorPolicy::FailFast)
+        .plan()
+        .unwrap();
+    assert_eq!(plan.shaders.len(), 2);
+    assert_eq!(plan.invalid_shaders.len(), 1);
+    assert!(is_duplicate_entry_point(&plan.invalid_shaders[0].error));
+
+    let error = stage(DuplicatePolicy::Error, ErrorPolicy::FailFast)
+        .build()
+        .err()
+        .expect("the second fragment entry point must fail the build");
+    assert!(is_duplicate_entry_point(&error));
+    assert!(device.created_modules().is_empty());
+
+    let shader_set = stage(DuplicatePolicy::Error, ErrorPolicy::Collect)
+        .build()
+        .unwrap();
+    assert_eq!(shader_set.failures().len(), 1);
+    assert!(is_duplicate_entry_point(&shader_set.failures()[0].error));
+    let fragment_entry_point = |shader_set: &ShaderSet<MockDevice>| {
+        let records = shader_set.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].stage, ShaderStageFlags::FRAGMENT);
+        records[1].entry_point.clone()
+    };
+    assert_eq!(fragment_entry_point(&shader_set), "PSMain");
+
+    let shader_set = stage(DuplicatePolicy::FirstWins, ErrorPolicy::FailFast)
+        .build()
+        .unwrap();
+    assert!(shader_set.failures().is_empty());
+    assert_eq!(fragment_entry_point(&shader_set), "PSMain");
+
+    let shader_set = stage(DuplicatePolicy::LastWins, ErrorPolicy::FailFast)
+        .build()
+        .unwrap();
+    assert_eq!(fragment_entry_point(&shader_set), "OtherPSMain");
+
+    // The override makes both fragment entry points the same one.
+    let shader_set = stage(DuplicatePolicy::Error, ErrorPolicy::FailFast)
+        .with_stage_entry_point(ShaderStageFlags::FRAGMENT, "OtherPSMain")
+        .build()
+        .unwrap();
+    assert_eq!(fragment_entry_point(&shader_set), "OtherPSMain");
+}
+
+#[test]
+fn file_name_stage_selects_one_entry_point() {
+    let device = MockDevice::new();
+    let code = spirv(&[(VERTEX, "VSMain"), (FRAGMENT, "PSMain")]);
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words("sky.frag.spv", None, &code)],
+    )
+    .with_stage_detection(StageDetection::FileNameFirst)
+    .build()
+    .unwrap();
+
+    let records = shader_set.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].stage, ShaderStageFlags::FRAGMENT);
+    assert_eq!(records[0].entry_point, "PSMain");
+    shader_set.destroy(&device);
+}
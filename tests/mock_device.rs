@@ -0,0 +1,145 @@
+mod common;
+
+use std::ffi::CStr;
+
+use ash::vk::{AllocationCallbacks, ShaderStageFlags};
+use ash_shader_creator::{MockDevice, ShaderStage, StageDetection};
+use common::{spirv, TempDir, FRAGMENT, GL_COMPUTE, VERTEX};
+
+/// `sky` program of two stages, `cull.spv` named by no convention and `mislabeled.vert.spv`
+/// whose module is a fragment shader.
+fn shaders_dir() -> TempDir {
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    dir.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir.write_words("cull.spv", &spirv(&[(GL_COMPUTE, "cull_main")]));
+    dir.write_words("mislabeled.vert.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir
+}
+
+#[test]
+fn directory_build_creates_a_module_for_every_compiled_shader() {
+    let dir = shaders_dir();
+    let device = MockDevice::new();
+
+    let shader_set = ShaderStage::new(&device, dir.path()).build().unwrap();
+
+    let stages: Vec<_> = shader_set
+        .records()
+        .iter()
+        .map(|record| (record.program_name.as_str(), record.stage))
+        .collect();
+    assert_eq!(
+        stages,
+        [
+            ("cull", ShaderStageFlags::COMPUTE),
+            ("mislabeled", ShaderStageFlags::FRAGMENT),
+            ("sky", ShaderStageFlags::VERTEX),
+            ("sky", ShaderStageFlags::FRAGMENT),
+        ]
+    );
+
+    let created_modules = device.created_modules();
+    assert_eq!(created_modules.len(), 4);
+    for (record, stage) in shader_set.records().iter().zip(shader_set.stages()) {
+        let recorded = created_modules
+            .iter()
+            .find(|recorded| recorded.module == record.module)
+            .expect("every record must have its created module");
+        assert_eq!(
+            recorded.code,
+            common::words_of_file(&record.path),
+            "{:?}",
+            record.path
+        );
+        assert!(!recorded.with_allocation_callbacks);
+        assert_eq!(stage.module, record.module);
+        assert_eq!(stage.stage, record.stage);
+        let entry_point = unsafe { CStr::from_ptr(stage.p_name) };
+        assert_eq!(entry_point.to_str(), Ok(record.entry_point.as_str()));
+    }
+    assert_eq!(shader_set.records()[0].entry_point, "cull_main");
+    assert_eq!(device.live_modules().len(), 4);
+}
+
+#[test]
+fn file_name_first_detection_maps_stages_from_the_names() {
+    let dir = shaders_dir();
+    let device = MockDevice::new();
+
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_stage_detection(StageDetection::FileNameFirst)
+        .build()
+        .unwrap();
+
+    let stages: Vec<_> = shader_set
+        .records()
+        .iter()
+        .map(|record| (record.program_name.as_str(), record.stage))
+        .collect();
+    // `cull.spv` follows no convention, so its stage still comes from SPIR-V.
+    assert_eq!(
+        stages,
+        [
+            ("cull", ShaderStageFlags::COMPUTE),
+            ("mislabeled", ShaderStageFlags::VERTEX),
+            ("sky", ShaderStageFlags::VERTEX),
+            ("sky", ShaderStageFlags::FRAGMENT),
+        ]
+    );
+}
+
+#[test]
+fn destroy_destroys_every_module_once() {
+    let dir = TempDir::new();
+    dir.write_words("sky.spv", &spirv(&[(VERTEX, "main"), (FRAGMENT, "main")]));
+    dir.write_words("cull.comp.spv", &spirv(&[(GL_COMPUTE, "main")]));
+    let device = MockDevice::new();
+    let allocation_callbacks = AllocationCallbacks::default();
+
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_allocation_callbacks(Some(&allocation_callbacks))
+        .build()
+        .unwrap();
+    assert_eq!(shader_set.stages().len(), 3);
+    assert_eq!(device.created_modules().len(), 2);
+
+    shader_set.destroy(&device);
+
+    assert!(device.live_modules().is_empty());
+    assert_eq!(device.destroyed_modules().len(), 2);
+    for recorded in device.created_modules() {
+        assert!(recorded.with_allocation_callbacks);
+        assert_eq!(recorded.destroyed_with_allocation_callbacks, Some(true));
+    }
+}
+
+#[test]
+fn modules_are_destroyed_on_drop_only_when_asked() {
+    let dir = shaders_dir();
+    let device = MockDevice::new();
+
+    let shader_set = ShaderStage::new(&device, dir.path()).build().unwrap();
+    drop(shader_set);
+    assert_eq!(device.live_modules().len(), 4);
+    assert!(device.destroyed_modules().is_empty());
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_destroy_on_drop(true)
+        .build()
+        .unwrap();
+    assert_eq!(device.live_modules().len(), 4);
+    drop(shader_set);
+    assert!(device.live_modules().is_empty());
+    assert_eq!(device.destroyed_modules().len(), 4);
+
+    // Destroying explicitly doesn't destroy the modules again on drop.
+    let device = MockDevice::new();
+    ShaderStage::new(&device, dir.path())
+        .with_destroy_on_drop(true)
+        .build()
+        .unwrap()
+        .destroy(&device);
+    assert_eq!(device.destroyed_modules().len(), 4);
+}
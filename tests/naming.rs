@@ -0,0 +1,134 @@
+mod common;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    IgnoreReason, MockDevice, NamingConvention, ShaderName, ShaderStage, SuffixConvention,
+};
+use common::{spirv, TempDir, FRAGMENT, GL_COMPUTE, VERTEX};
+
+fn name(program_name: &str, stage: ShaderStageFlags, variant: Option<&str>) -> Option<ShaderName> {
+    Some(ShaderName {
+        program_name: program_name.to_owned(),
+        stage,
+        variant: variant.map(str::to_owned),
+    })
+}
+
+#[test]
+fn suffixes_are_matched_case_insensitively_at_the_end() {
+    let glslang = SuffixConvention::glslang();
+    assert_eq!(
+        glslang.parse("sky.vert.spv"),
+        name("sky", ShaderStageFlags::VERTEX, None)
+    );
+    assert_eq!(
+        glslang.parse("Sky.FRAG.Spv"),
+        name("Sky", ShaderStageFlags::FRAGMENT, None)
+    );
+    assert_eq!(
+        glslang.parse("sky.rchit.spv"),
+        name("sky", ShaderStageFlags::CLOSEST_HIT_KHR, None)
+    );
+    assert_eq!(glslang.parse("sky.vert"), None);
+    assert_eq!(glslang.parse("sky.vert.spv.bak"), None);
+    assert_eq!(glslang.parse("vert.spv"), None);
+    assert_eq!(glslang.parse(".vert.spv"), None);
+    assert_eq!(glslang.parse("sky_vert.spv"), None);
+
+    let dxc = SuffixConvention::dxc();
+    assert_eq!(
+        dxc.parse("sky.vs"),
+        name("sky", ShaderStageFlags::VERTEX, None)
+    );
+    assert_eq!(
+        dxc.parse("sky.PS.spv"),
+        name("sky", ShaderStageFlags::FRAGMENT, None)
+    );
+    assert_eq!(
+        dxc.parse("sky.cs.cso"),
+        name("sky", ShaderStageFlags::COMPUTE, None)
+    );
+    assert_eq!(dxc.parse("sky.vs.txt"), None);
+}
+
+#[test]
+fn variants_and_underscore_separators_are_parsed() {
+    let glslang = SuffixConvention::glslang();
+    assert_eq!(
+        glslang.parse("sky.shadow.vert.spv"),
+        name("sky", ShaderStageFlags::VERTEX, Some("shadow"))
+    );
+    assert_eq!(
+        glslang.parse("sky.shadow.msaa.frag.spv"),
+        name("sky", ShaderStageFlags::FRAGMENT, Some("shadow.msaa"))
+    );
+
+    let suffix_before_spv = SuffixConvention::suffix_before_spv();
+    assert_eq!(
+        suffix_before_spv.parse("sky_vs.spv"),
+        name("sky", ShaderStageFlags::VERTEX, None)
+    );
+    assert_eq!(
+        suffix_before_spv.parse("sky.shadow_PS.spv"),
+        name("sky", ShaderStageFlags::FRAGMENT, Some("shadow"))
+    );
+    assert_eq!(
+        suffix_before_spv.parse("sky.comp.spv"),
+        name("sky", ShaderStageFlags::COMPUTE, None)
+    );
+    assert_eq!(suffix_before_spv.parse("sky_vs"), None);
+
+    let custom = SuffixConvention::new(&[("pixel", ShaderStageFlags::FRAGMENT)], &["-"], &["bin"]);
+    assert_eq!(
+        custom.parse("sky-pixel.bin"),
+        name("sky", ShaderStageFlags::FRAGMENT, None)
+    );
+    assert_eq!(custom.parse("sky.pixel.bin"), None);
+}
+
+#[test]
+fn files_without_the_spv_extension_must_be_spirv() {
+    let dir = TempDir::new();
+    dir.write_words("sky.vs", &spirv(&[(VERTEX, "main")]));
+    dir.write_words("sky.ps", &spirv(&[(FRAGMENT, "main")]));
+    dir.write("Program.cs", b"class Program { static void Main() {} }");
+    dir.write("Empty.gs", b"");
+    dir.write_words("cull.cs.spv", &spirv(&[(GL_COMPUTE, "main")]));
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path()).plan().unwrap();
+    let planned: Vec<_> = plan
+        .shaders
+        .iter()
+        .map(|shader| (shader.program_name.as_str(), shader.stage))
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            ("cull", ShaderStageFlags::COMPUTE),
+            ("sky", ShaderStageFlags::VERTEX),
+            ("sky", ShaderStageFlags::FRAGMENT),
+        ]
+    );
+    let ignored: Vec<_> = plan
+        .ignored_files
+        .iter()
+        .map(|ignored_file| {
+            (
+                ignored_file.path.file_name().unwrap().to_str().unwrap(),
+                ignored_file.reason,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ignored,
+        [
+            ("Empty.gs", IgnoreReason::NotShader),
+            ("Program.cs", IgnoreReason::NotShader),
+        ]
+    );
+    assert!(plan.is_valid());
+
+    let shader_set = ShaderStage::new(&device, dir.path()).build().unwrap();
+    assert_eq!(shader_set.records().len(), 3);
+}
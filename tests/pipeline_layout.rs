@@ -0,0 +1,368 @@
+mod common;
+
+use ash::vk::{
+    self, AllocationCallbacks, DescriptorBindingFlags, DescriptorSetLayoutCreateFlags,
+    DescriptorType, Handle, Sampler, ShaderStageFlags,
+};
+use ash_shader_creator::{
+    BindingConfig, MockDevice, PipelineLayoutConfig, RecordedDescriptorSetLayoutBinding,
+    ShaderCreatorError, ShaderSource, ShaderStage,
+};
+use common::{
+    Module, DECORATION_BLOCK, DECORATION_MATRIX_STRIDE, DIM_2D, FRAGMENT, STORAGE_CLASS_UNIFORM,
+    STORAGE_CLASS_UNIFORM_CONSTANT, VERTEX,
+};
+
+/// The vertex stage uses the camera at the set 0 and the push constants,
+/// the fragment stage uses the textures at the set 2, so the set 1 is empty.
+fn sources() -> Vec<ShaderSource> {
+    let mut vertex = Module::new().entry_point(VERTEX, "main");
+    let camera = vertex.type_block(DECORATION_BLOCK);
+    vertex.descriptor(0, 0, "camera", STORAGE_CLASS_UNIFORM, camera);
+    let float = vertex.type_float(32);
+    let vec4 = vertex.type_vector(float, 4);
+    let mat4 = vertex.type_matrix(vec4, 4);
+    let block = vertex.push_constant_block("Constants", &[("transform", mat4, 0)]);
+    vertex.member_decorate(block, 0, DECORATION_MATRIX_STRIDE, &[16]);
+
+    let mut fragment = Module::new().entry_point(FRAGMENT, "main");
+    let image = fragment.type_image(DIM_2D, 1);
+    let sampled_image = fragment.type_sampled_image(image);
+    fragment.descriptor(
+        2,
+        0,
+        "shadow",
+        STORAGE_CLASS_UNIFORM_CONSTANT,
+        sampled_image,
+    );
+    let textures = fragment.type_array(sampled_image, None);
+    fragment.descriptor(2, 1, "textures", STORAGE_CLASS_UNIFORM_CONSTANT, textures);
+
+    vec![
+        ShaderSource::from_words("sky.vert.spv", None, &vertex.words()),
+        ShaderSource::from_words("sky.frag.spv", None, &fragment.words()),
+    ]
+}
+
+/// The count of the runtime array of textures.
+fn texture_count() -> PipelineLayoutConfig {
+    PipelineLayoutConfig::new().with_binding(2, 1, BindingConfig::new().with_descriptor_count(16))
+}
+
+fn binding(
+    binding: u32,
+    descriptor_type: DescriptorType,
+    descriptor_count: u32,
+    stage_flags: ShaderStageFlags,
+) -> RecordedDescriptorSetLayoutBinding {
+    RecordedDescriptorSetLayoutBinding {
+        binding,
+        descriptor_type,
+        descriptor_count,
+        stage_flags,
+        immutable_samplers: Vec::new(),
+        binding_flags: DescriptorBindingFlags::empty(),
+    }
+}
+
+#[test]
+fn pipeline_layout_is_created_from_reflection() {
+    let device = MockDevice::new();
+    let allocation_callbacks = AllocationCallbacks::default();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .with_allocation_callbacks(Some(&allocation_callbacks))
+        .build()
+        .unwrap();
+
+    let layout = shader_set
+        .program("sky")
+        .unwrap()
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap();
+
+    let descriptor_set_layouts = device.created_descriptor_set_layouts();
+    let bindings: Vec<_> = descriptor_set_layouts
+        .iter()
+        .map(|recorded| recorded.bindings.clone())
+        .collect();
+    assert_eq!(
+        bindings,
+        [
+            vec![binding(
+                0,
+                DescriptorType::UNIFORM_BUFFER,
+                1,
+                ShaderStageFlags::VERTEX
+            )],
+            vec![],
+            vec![
+                binding(
+                    0,
+                    DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    1,
+                    ShaderStageFlags::FRAGMENT
+                ),
+                binding(
+                    1,
+                    DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    16,
+                    ShaderStageFlags::FRAGMENT
+                ),
+            ],
+        ]
+    );
+    assert!(descriptor_set_layouts
+        .iter()
+        .all(|recorded| recorded.flags.is_empty() && recorded.with_allocation_callbacks));
+
+    let pipeline_layouts = device.created_pipeline_layouts();
+    assert_eq!(pipeline_layouts.len(), 1);
+    assert_eq!(pipeline_layouts[0].layout, layout.pipeline_layout);
+    assert_eq!(
+        pipeline_layouts[0].set_layouts,
+        layout.descriptor_set_layouts
+    );
+    let ranges: Vec<_> = pipeline_layouts[0]
+        .push_constant_ranges
+        .iter()
+        .map(|range| (range.stage_flags, range.offset, range.size))
+        .collect();
+    assert_eq!(ranges, [(ShaderStageFlags::VERTEX, 0, 64)]);
+
+    // The layouts outlive the shader modules.
+    shader_set.destroy(&device);
+    assert!(device.live_modules().is_empty());
+    assert_eq!(device.live_descriptor_set_layouts().len(), 3);
+    assert_eq!(device.live_pipeline_layouts().len(), 1);
+
+    layout.destroy(&device);
+    assert!(device.live_descriptor_set_layouts().is_empty());
+    assert!(device.live_pipeline_layouts().is_empty());
+    assert!(device
+        .created_descriptor_set_layouts()
+        .iter()
+        .all(|recorded| recorded.destroyed_with_allocation_callbacks == Some(true)));
+    assert_eq!(
+        device.created_pipeline_layouts()[0].destroyed_with_allocation_callbacks,
+        Some(true)
+    );
+}
+
+#[test]
+fn config_overrides_reflected_bindings() {
+    let samplers = vec![Sampler::from_raw(7)];
+    let config = PipelineLayoutConfig::new()
+        .with_binding(
+            0,
+            0,
+            BindingConfig::new().with_descriptor_type(DescriptorType::UNIFORM_BUFFER_DYNAMIC),
+        )
+        .with_binding(
+            2,
+            0,
+            BindingConfig::new().with_immutable_samplers(samplers.clone()),
+        )
+        .with_binding(
+            2,
+            1,
+            BindingConfig::new()
+                .with_descriptor_count(16)
+                .with_binding_flags(DescriptorBindingFlags::PARTIALLY_BOUND),
+        )
+        .with_binding(
+            2,
+            1,
+            BindingConfig::new().with_binding_flags(DescriptorBindingFlags::UPDATE_AFTER_BIND),
+        )
+        .with_binding(3, 0, BindingConfig::new().with_descriptor_count(4))
+        .with_set_flags(2, DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL);
+
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+    let layout = shader_set
+        .program("sky")
+        .unwrap()
+        .create_pipeline_layout_with_config(&device, &config)
+        .unwrap();
+
+    let descriptor_set_layouts = device.created_descriptor_set_layouts();
+    assert_eq!(descriptor_set_layouts.len(), 3);
+    assert_eq!(
+        descriptor_set_layouts[0].bindings,
+        [binding(
+            0,
+            DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+            1,
+            ShaderStageFlags::VERTEX
+        )]
+    );
+    assert_eq!(
+        descriptor_set_layouts[2].flags,
+        DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL
+    );
+    assert_eq!(
+        descriptor_set_layouts[2].bindings,
+        [
+            RecordedDescriptorSetLayoutBinding {
+                immutable_samplers: samplers,
+                ..binding(
+                    0,
+                    DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    1,
+                    ShaderStageFlags::FRAGMENT
+                )
+            },
+            RecordedDescriptorSetLayoutBinding {
+                binding_flags: DescriptorBindingFlags::UPDATE_AFTER_BIND,
+                ..binding(
+                    1,
+                    DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    16,
+                    ShaderStageFlags::FRAGMENT
+                )
+            },
+        ]
+    );
+
+    layout.destroy(&device);
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn layouts_are_not_destroyed_with_the_set_on_drop() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .with_destroy_on_drop(true)
+        .build()
+        .unwrap();
+    let sky = shader_set.program("sky").unwrap();
+    let first_layout = sky
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap();
+    let second_layout = sky
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap();
+
+    drop(shader_set);
+    assert!(device.live_modules().is_empty());
+    assert_eq!(device.live_pipeline_layouts().len(), 2);
+
+    first_layout.destroy(&device);
+    assert_eq!(
+        device.live_pipeline_layouts(),
+        [second_layout.pipeline_layout]
+    );
+    second_layout.destroy(&device);
+    assert!(device.live_pipeline_layouts().is_empty());
+    assert!(device.live_descriptor_set_layouts().is_empty());
+}
+
+#[test]
+fn failed_layout_creation_destroys_created_layouts() {
+    let device = MockDevice::failing_layouts_after(2, vk::Result::ERROR_OUT_OF_HOST_MEMORY);
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+    let sky = shader_set.program("sky").unwrap();
+
+    match sky
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap_err()
+    {
+        ShaderCreatorError::CreateDescriptorSetLayout {
+            program_name,
+            variant,
+            set,
+            result,
+        } => {
+            assert_eq!(program_name, "sky");
+            assert_eq!(variant, None);
+            assert_eq!(set, 2);
+            assert_eq!(result, vk::Result::ERROR_OUT_OF_HOST_MEMORY);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert_eq!(device.created_descriptor_set_layouts().len(), 2);
+    assert!(device.live_descriptor_set_layouts().is_empty());
+
+    shader_set.destroy(&device);
+    assert!(device.live_modules().is_empty());
+}
+
+#[test]
+fn failed_pipeline_layout_creation_destroys_descriptor_set_layouts() {
+    let device = MockDevice::failing_layouts_after(3, vk::Result::ERROR_OUT_OF_DEVICE_MEMORY);
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+
+    let error = shader_set
+        .program("sky")
+        .unwrap()
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ShaderCreatorError::CreatePipelineLayout {
+            result: vk::Result::ERROR_OUT_OF_DEVICE_MEMORY,
+            ..
+        }
+    ));
+    assert_eq!(device.created_descriptor_set_layouts().len(), 3);
+    assert!(device.live_descriptor_set_layouts().is_empty());
+    assert!(device.created_pipeline_layouts().is_empty());
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn runtime_arrays_without_configured_count_are_unresolved() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+
+    match shader_set
+        .program("sky")
+        .unwrap()
+        .create_pipeline_layout(&device)
+        .unwrap_err()
+    {
+        ShaderCreatorError::UnresolvedDescriptorCount {
+            program_name,
+            variant,
+            set,
+            binding,
+        } => {
+            assert_eq!(program_name, "sky");
+            assert_eq!(variant, None);
+            assert_eq!((set, binding), (2, 1));
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert!(device.created_descriptor_set_layouts().is_empty());
+    assert!(device.created_pipeline_layouts().is_empty());
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn dropping_layout_without_destroy_leaks_the_layouts() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .with_destroy_on_drop(true)
+        .build()
+        .unwrap();
+    let layout = shader_set
+        .program("sky")
+        .unwrap()
+        .create_pipeline_layout_with_config(&device, &texture_count())
+        .unwrap();
+    let pipeline_layout = layout.pipeline_layout;
+
+    drop(layout);
+    assert_eq!(device.live_pipeline_layouts(), [pipeline_layout]);
+    assert_eq!(device.live_descriptor_set_layouts().len(), 3);
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use std::path::PathBuf;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    IgnoreReason, MockDevice, ShaderCreatorError, ShaderSource, ShaderStage,
+    SpecializationConstants, StageConfig, StageSelector,
+};
+use common::{
+    spirv, Module, TempDir, DECORATION_BLOCK, FRAGMENT, STORAGE_CLASS_STORAGE_BUFFER,
+    STORAGE_CLASS_UNIFORM, VERTEX,
+};
+
+/// Valid `sky` program, and every kind of the invalid compiled shaders.
+fn shaders_dir() -> TempDir {
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    dir.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir.write("notes.txt", b"Compiled with glslangValidator.");
+
+    let mut truncated = spirv(&[(VERTEX, "main")]);
+    truncated.pop();
+    dir.write_words("sea.vert.spv", &truncated);
+    let mut zero_word_count = spirv(&[(FRAGMENT, "main")]);
+    zero_word_count.push(0);
+    dir.write_words("sea.frag.spv", &zero_word_count);
+    dir.write("sun.frag.spv", &[0; 22]);
+    let mut bad_magic = spirv(&[(FRAGMENT, "main")]);
+    bad_magic[0] = 0;
+    dir.write_words("moon.frag.spv", &bad_magic);
+    dir.write_words("unknown.spv", &spirv(&[]));
+    dir
+}
+
+#[test]
+fn plan_reports_planned_ignored_and_invalid_shaders() {
+    let dir = shaders_dir();
+
+    let plan = ShaderStage::without_device()
+        .with_dir_path(dir.path())
+        .plan()
+        .unwrap();
+
+    let planned: Vec<_> = plan
+        .shaders
+        .iter()
+        .map(|shader| {
+            (
+                shader.path.file_name().unwrap().to_str().unwrap(),
+                shader.program_name.as_str(),
+                shader.stage,
+                shader.entry_point.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            ("sky.vert.spv", "sky", ShaderStageFlags::VERTEX, "main"),
+            ("sky.frag.spv", "sky", ShaderStageFlags::FRAGMENT, "main"),
+        ]
+    );
+
+    assert_eq!(plan.ignored_files.len(), 1);
+    assert_eq!(plan.ignored_files[0].path, dir.path().join("notes.txt"));
+    assert_eq!(plan.ignored_files[0].reason, IgnoreReason::NotShader);
+
+    let invalid: Vec<_> = plan
+        .invalid_shaders
+        .iter()
+        .map(|invalid_shader| {
+            assert_eq!(
+                invalid_shader.error.path(),
+                Some(invalid_shader.path.as_path())
+            );
+            let kind = match invalid_shader.error {
+                ShaderCreatorError::InvalidSpirvMagic { .. } => "magic",
+                ShaderCreatorError::MalformedSpirv { .. } => "malformed",
+                ShaderCreatorError::InvalidSpirvSize { size: 22, .. } => "size",
+                ShaderCreatorError::UnknownStage { .. } => "stage",
+                ref error => panic!("unexpected error: {}", error),
+            };
+            (
+                invalid_shader.path.file_name().unwrap().to_str().unwrap(),
+                kind,
+            )
+        })
+        .collect();
+    assert_eq!(
+        invalid,
+        [
+            ("moon.frag.spv", "magic"),
+            ("sea.frag.spv", "malformed"),
+            ("sea.vert.spv", "malformed"),
+            ("sun.frag.spv", "size"),
+            ("unknown.spv", "stage"),
+        ]
+    );
+    assert!(plan.invalid_programs.is_empty());
+    assert!(!plan.is_valid());
+}
+
+#[test]
+fn build_fails_on_the_first_invalid_shader_of_the_plan() {
+    let dir = shaders_dir();
+    let device = MockDevice::new();
+
+    let error = ShaderStage::new(&device, dir.path())
+        .build()
+        .err()
+        .expect("invalid shaders must fail the build");
+    assert!(matches!(
+        error,
+        ShaderCreatorError::InvalidSpirvMagic { .. }
+    ));
+    assert!(device.created_modules().is_empty());
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_exclude_pattern("s[eu]*")
+        .with_exclude_pattern("moon.*")
+        .with_exclude_pattern("unknown.spv")
+        .plan()
+        .unwrap();
+    assert!(plan.is_valid());
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_exclude_pattern("s[eu]*")
+        .with_exclude_pattern("moon.*")
+        .with_exclude_pattern("unknown.spv")
+        .build()
+        .unwrap();
+    let planned: Vec<_> = plan
+        .shaders
+        .iter()
+        .map(|shader| (shader.path.as_path(), shader.stage))
+        .collect();
+    let built: Vec<_> = shader_set
+        .records()
+        .iter()
+        .map(|record| (record.path.as_path(), record.stage))
+        .collect();
+    assert_eq!(planned, built);
+}
+
+#[test]
+fn plan_reports_specialization_and_layout_mismatches() {
+    let sources = || {
+        let mut sky_vertex = Module::new().entry_point(VERTEX, "main");
+        let camera = sky_vertex.type_block(DECORATION_BLOCK);
+        sky_vertex.descriptor(0, 0, "camera", STORAGE_CLASS_UNIFORM, camera);
+        let mut sky_fragment = Module::new().entry_point(FRAGMENT, "main");
+        let camera = sky_fragment.type_block(DECORATION_BLOCK);
+        sky_fragment.descriptor(0, 0, "camera", STORAGE_CLASS_STORAGE_BUFFER, camera);
+
+        let mut sun_vertex = Module::new().entry_point(VERTEX, "main");
+        let float = sun_vertex.type_float(32);
+        sun_vertex.push_constant_block("Constants", &[("time", float, 0)]);
+        let mut sun_fragment = Module::new().entry_point(FRAGMENT, "main");
+        let int = sun_fragment.type_int(32, true);
+        sun_fragment.push_constant_block("Constants", &[("frame", int, 0)]);
+
+        vec![
+            ShaderSource::from_words("sky.vert.spv", None, &sky_vertex.words()),
+            ShaderSource::from_words("sky.frag.spv", None, &sky_fragment.words()),
+            ShaderSource::from_words("sea.vert.spv", None, &spirv(&[(VERTEX, "main")])),
+            ShaderSource::from_words("sea.frag.spv", None, &spirv(&[(FRAGMENT, "main")])),
+            ShaderSource::from_words("sun.vert.spv", None, &sun_vertex.words()),
+            ShaderSource::from_words("sun.frag.spv", None, &sun_fragment.words()),
+        ]
+    };
+    let sea_fragment_config = || {
+        StageConfig::new()
+            .with_specialization_constants(SpecializationConstants::new().set(3, 1u32))
+    };
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::from_sources(&device, sources())
+        .with_stage_config(
+            StageSelector::File(PathBuf::from("sea.frag.spv")),
+            sea_fragment_config(),
+        )
+        .plan()
+        .unwrap();
+    assert!(!plan.is_valid());
+    let planned: Vec<_> = plan
+        .shaders
+        .iter()
+        .map(|shader| shader.path.to_str().unwrap())
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            "sea.vert.spv",
+            "sky.vert.spv",
+            "sky.frag.spv",
+            "sun.vert.spv",
+            "sun.frag.spv"
+        ]
+    );
+    let invalid_shaders: Vec<_> = plan
+        .invalid_shaders
+        .iter()
+        .map(|invalid_shader| {
+            assert!(matches!(
+                invalid_shader.error,
+                ShaderCreatorError::UnknownSpecializationConstant { .. }
+            ));
+            invalid_shader.path.to_str().unwrap()
+        })
+        .collect();
+    assert_eq!(invalid_shaders, ["sea.frag.spv"]);
+    // Shaders of the invalid programs are still created, only their layouts fail.
+    let invalid_programs: Vec<_> = plan
+        .invalid_programs
+        .iter()
+        .map(|invalid_program| {
+            let kind = match invalid_program.error {
+                ShaderCreatorError::DescriptorTypeMismatch { .. } => "descriptor",
+                ShaderCreatorError::PushConstantMismatch { .. } => "push constant",
+                ref error => panic!("unexpected error: {}", error),
+            };
+            (
+                invalid_program.program_name.as_str(),
+                invalid_program.variant.as_deref(),
+                invalid_program.error.path().unwrap().to_str().unwrap(),
+                kind,
+            )
+        })
+        .collect();
+    assert_eq!(
+        invalid_programs,
+        [
+            ("sky", None, "sky.frag.spv", "descriptor"),
+            ("sun", None, "sun.frag.spv", "push constant"),
+        ]
+    );
+    assert!(device.created_modules().is_empty());
+
+    // `build` fails on the same inputs.
+    let error = ShaderStage::from_sources(&device, sources())
+        .with_stage_config(
+            StageSelector::File(PathBuf::from("sea.frag.spv")),
+            sea_fragment_config(),
+        )
+        .build()
+        .err()
+        .expect("sea.frag.spv declares no constants");
+    assert!(matches!(
+        error,
+        ShaderCreatorError::UnknownSpecializationConstant { .. }
+    ));
+}
@@ -0,0 +1,308 @@
+mod common;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    MockDevice, PushConstantBlockInfo, PushConstantMemberInfo, ShaderCreatorError, ShaderSource,
+    ShaderStage,
+};
+use common::{
+    Module, DECORATION_ARRAY_STRIDE, DECORATION_MATRIX_STRIDE, DECORATION_ROW_MAJOR, FRAGMENT,
+    VERTEX,
+};
+
+/// The module with the push constant block of `mat4 transform` at the offset 0 and the members after it.
+fn module(execution_model: u32, members: &[(&str, u32)]) -> Vec<u32> {
+    let mut module = Module::new().entry_point(execution_model, "main");
+    let float = module.type_float(32);
+    let vec4 = module.type_vector(float, 4);
+    let mat4 = module.type_matrix(vec4, 4);
+    let mut block_members = vec![("transform", mat4, 0)];
+    for (name, offset) in members {
+        block_members.push((name, vec4, *offset));
+    }
+    let block = module.push_constant_block("Constants", &block_members);
+    module.member_decorate(block, 0, DECORATION_MATRIX_STRIDE, &[16]);
+    module.words()
+}
+
+fn sources() -> Vec<ShaderSource> {
+    vec![
+        ShaderSource::from_words("sky.vert.spv", None, &module(VERTEX, &[])),
+        ShaderSource::from_words("sky.frag.spv", None, &module(FRAGMENT, &[])),
+        ShaderSource::from_words("sea.vert.spv", None, &module(VERTEX, &[])),
+        ShaderSource::from_words("sea.frag.spv", None, &module(FRAGMENT, &[("color", 64)])),
+        ShaderSource::from_words("sun.vert.spv", None, &module(VERTEX, &[])),
+        ShaderSource::from_words("sun.frag.spv", None, &module(FRAGMENT, &[("glow", 32)])),
+    ]
+}
+
+#[test]
+fn push_constant_blocks_are_reflected() {
+    let mut module = Module::new().entry_point(VERTEX, "main");
+    let float = module.type_float(32);
+    let int = module.type_int(32, true);
+    let vec3 = module.type_vector(float, 3);
+    let ivec2 = module.type_vector(int, 2);
+    let mat2x3 = module.type_matrix(vec3, 2);
+    let weights = module.type_array(float, Some(3));
+    module.decorate(weights, DECORATION_ARRAY_STRIDE, &[16]);
+    let block = module.push_constant_block(
+        "Constants",
+        &[
+            ("offset", ivec2, 16),
+            ("basis", mat2x3, 32),
+            ("weights", weights, 80),
+        ],
+    );
+    module.member_decorate(block, 1, DECORATION_MATRIX_STRIDE, &[16]);
+    module.member_decorate(block, 1, DECORATION_ROW_MAJOR, &[]);
+
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words(
+            "sky.vert.spv",
+            None,
+            &module.words(),
+        )],
+    )
+    .load_records()
+    .unwrap();
+
+    let member = |name: &str, offset, size, type_name: &str| PushConstantMemberInfo {
+        name: Some(String::from(name)),
+        offset,
+        size,
+        type_name: String::from(type_name),
+    };
+    assert_eq!(
+        records[0].reflection.push_constant_blocks,
+        [PushConstantBlockInfo {
+            name: Some(String::from("Constants")),
+            offset: 16,
+            size: 112,
+            members: vec![
+                member("offset", 16, 8, "ivec2"),
+                member("basis", 32, 48, "mat2x3"),
+                member("weights", 80, 48, "float[3]"),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn push_constant_ranges_are_merged_across_stages() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+
+    let ranges = |program_name| {
+        shader_set
+            .program(program_name)
+            .unwrap()
+            .push_constant_ranges()
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .map(|range| (range.stage_flags, range.offset, range.size))
+                    .collect::<Vec<_>>()
+            })
+    };
+
+    assert_eq!(
+        ranges("sky").unwrap(),
+        [(ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT, 0, 64)]
+    );
+    assert_eq!(
+        ranges("sea").unwrap(),
+        [
+            (ShaderStageFlags::VERTEX, 0, 64),
+            (ShaderStageFlags::FRAGMENT, 0, 80),
+        ]
+    );
+
+    match ranges("sun").expect_err("the glow overlaps the transform") {
+        ShaderCreatorError::PushConstantMismatch {
+            path,
+            stage,
+            offset,
+            size,
+            type_name,
+            other_stage,
+            other_offset,
+            other_size,
+            other_type_name,
+        } => {
+            assert_eq!(path.to_str(), Some("sun.frag.spv"));
+            assert_eq!(stage, ShaderStageFlags::FRAGMENT);
+            assert_eq!((offset, size, type_name.as_str()), (32, 16, "vec4"));
+            assert_eq!(other_stage, ShaderStageFlags::VERTEX);
+            assert_eq!(
+                (other_offset, other_size, other_type_name.as_str()),
+                (0, 64, "mat4")
+            );
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn members_of_the_same_bytes_must_have_the_same_type() {
+    let module = |execution_model, signed: Option<bool>| {
+        let mut module = Module::new().entry_point(execution_model, "main");
+        let scale = match signed {
+            Some(signed) => module.type_int(32, signed),
+            None => module.type_float(32),
+        };
+        module.push_constant_block("Constants", &[("scale", scale, 0)]);
+        module.words()
+    };
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_words("sky.vert.spv", None, &module(VERTEX, None)),
+            ShaderSource::from_words("sky.frag.spv", None, &module(FRAGMENT, None)),
+            ShaderSource::from_words("sea.vert.spv", None, &module(VERTEX, None)),
+            ShaderSource::from_words("sea.frag.spv", None, &module(FRAGMENT, Some(true))),
+            ShaderSource::from_words("sun.vert.spv", None, &module(VERTEX, Some(false))),
+            ShaderSource::from_words("sun.frag.spv", None, &module(FRAGMENT, Some(true))),
+        ],
+    )
+    .build()
+    .unwrap();
+    let type_names = |program_name| match shader_set
+        .program(program_name)
+        .unwrap()
+        .push_constant_ranges()
+    {
+        Ok(_) => None,
+        Err(ShaderCreatorError::PushConstantMismatch {
+            offset,
+            size,
+            type_name,
+            other_offset,
+            other_size,
+            other_type_name,
+            ..
+        }) => {
+            assert_eq!((offset, size), (other_offset, other_size));
+            Some((type_name, other_type_name))
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+    };
+
+    assert_eq!(type_names("sky"), None);
+    assert_eq!(
+        type_names("sea"),
+        Some((String::from("int"), String::from("float")))
+    );
+    assert_eq!(
+        type_names("sun"),
+        Some((String::from("int"), String::from("uint")))
+    );
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn entry_points_of_one_module_get_their_own_push_constant_blocks() {
+    let module = Module::new().entry_point(VERTEX, "VSMain");
+    let vertex_main = module.entry_point_function();
+    let mut module = module.entry_point(FRAGMENT, "PSMain");
+    let fragment_main = module.entry_point_function();
+    let float = module.type_float(32);
+    let int = module.type_int(32, true);
+    let vec4 = module.type_vector(float, 4);
+    let ivec4 = module.type_vector(int, 4);
+    let (_, vertex_constants) =
+        module.push_constant_variable("VSConstants", &[("offset", vec4, 0)]);
+    let (_, fragment_constants) =
+        module.push_constant_variable("PSConstants", &[("material", ivec4, 0)]);
+    module.function(vertex_main, &[vertex_constants], &[]);
+    module.function(fragment_main, &[fragment_constants], &[]);
+    let sources = || vec![ShaderSource::from_words("scene.spv", None, &module.words())];
+
+    let device = MockDevice::new();
+    let plan = ShaderStage::from_sources(&device, sources())
+        .plan()
+        .unwrap();
+    assert!(plan.is_valid());
+
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .build()
+        .unwrap();
+    let blocks: Vec<_> = shader_set
+        .records()
+        .iter()
+        .map(|record| {
+            let blocks = &record.reflection.push_constant_blocks;
+            assert_eq!(blocks.len(), 1);
+            (
+                record.stage,
+                blocks[0].name.as_deref(),
+                blocks[0].members[0].type_name.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        [
+            (ShaderStageFlags::VERTEX, Some("VSConstants"), "vec4"),
+            (ShaderStageFlags::FRAGMENT, Some("PSConstants"), "ivec4"),
+        ]
+    );
+
+    let ranges: Vec<_> = shader_set
+        .program("scene")
+        .unwrap()
+        .push_constant_ranges()
+        .unwrap()
+        .iter()
+        .map(|range| (range.stage_flags, range.offset, range.size))
+        .collect();
+    assert_eq!(
+        ranges,
+        [(ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT, 0, 16)]
+    );
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn push_constant_blocks_overflowing_u32_are_malformed() {
+    let mut large_array = Module::new().entry_point(VERTEX, "main");
+    let float = large_array.type_float(32);
+    let vec4 = large_array.type_vector(float, 4);
+    let offsets = large_array.type_array(vec4, Some(1 << 30));
+    large_array.decorate(offsets, DECORATION_ARRAY_STRIDE, &[16]);
+    large_array.push_constant_block("Constants", &[("offsets", offsets, 0)]);
+
+    let mut large_offset = Module::new().entry_point(VERTEX, "main");
+    let float = large_offset.type_float(32);
+    let vec4 = large_offset.type_vector(float, 4);
+    large_offset.push_constant_block("Constants", &[("offset", vec4, u32::MAX - 4)]);
+
+    for module in [large_array, large_offset] {
+        let device = MockDevice::new();
+        let error = ShaderStage::from_sources(
+            &device,
+            vec![ShaderSource::from_words(
+                "scene.vert.spv",
+                None,
+                &module.words(),
+            )],
+        )
+        .load_records()
+        .expect_err("the size of the block overflows");
+        match error {
+            ShaderCreatorError::MalformedSpirv { path } => {
+                assert_eq!(path.to_str(), Some("scene.vert.spv"))
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use ash::vk::{self, AllocationCallbacks};
+use ash_shader_creator::{ErrorPolicy, MockDevice, ShaderCreatorError, ShaderSource, ShaderStage};
+use common::{spirv, VERTEX};
+
+fn sources() -> Vec<ShaderSource> {
+    ["sky", "sea", "sun", "moon", "star"]
+        .iter()
+        .map(|name| ShaderSource::from_words(name, None, &spirv(&[(VERTEX, "main")])))
+        .collect()
+}
+
+#[test]
+fn failed_module_creation_destroys_created_modules() {
+    let device = MockDevice::failing_after(2, vk::Result::ERROR_OUT_OF_DEVICE_MEMORY);
+    let allocation_callbacks = AllocationCallbacks::default();
+
+    let error = ShaderStage::from_sources(&device, sources())
+        .with_allocation_callbacks(Some(&allocation_callbacks))
+        .build()
+        .err()
+        .expect("the third module must fail");
+
+    match error {
+        ShaderCreatorError::CreateShaderModule { path, result } => {
+            assert_eq!(path.to_str(), Some("sun"));
+            assert_eq!(result, vk::Result::ERROR_OUT_OF_DEVICE_MEMORY);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+
+    let created_modules = device.created_modules();
+    assert_eq!(created_modules.len(), 2);
+    assert!(device.live_modules().is_empty());
+    for recorded in created_modules {
+        assert!(recorded.with_allocation_callbacks);
+        assert_eq!(recorded.destroyed_with_allocation_callbacks, Some(true));
+    }
+}
+
+#[test]
+fn failed_load_records_destroys_created_modules() {
+    let device = MockDevice::failing_after(4, vk::Result::ERROR_OUT_OF_HOST_MEMORY);
+
+    let result = ShaderStage::from_sources(&device, sources()).load_records();
+
+    assert!(result.is_err());
+    assert_eq!(device.created_modules().len(), 4);
+    assert_eq!(device.destroyed_modules().len(), 4);
+    assert!(device.live_modules().is_empty());
+}
+
+#[test]
+fn invalid_shader_creates_no_modules() {
+    let device = MockDevice::new();
+    let mut sources = sources();
+    sources.insert(2, ShaderSource::from_bytes("broken", None, &[0; 7]));
+
+    let error = ShaderStage::from_sources(&device, sources)
+        .build()
+        .err()
+        .expect("the invalid shader must fail");
+
+    assert!(matches!(
+        error,
+        ShaderCreatorError::InvalidSpirvSize { size: 7, .. }
+    ));
+    assert!(device.created_modules().is_empty());
+}
+
+#[test]
+fn collect_policy_skips_failed_shaders() {
+    let device = MockDevice::failing_after(4, vk::Result::ERROR_OUT_OF_DEVICE_MEMORY);
+    let mut sources = sources();
+    sources.insert(2, ShaderSource::from_bytes("broken", None, &[0; 7]));
+
+    let shader_set = ShaderStage::from_sources(&device, sources)
+        .with_error_policy(ErrorPolicy::Collect)
+        .build()
+        .expect("failed shaders must be collected");
+
+    let failed_paths: Vec<_> = shader_set
+        .failures()
+        .iter()
+        .map(|failure| failure.path.to_str().unwrap())
+        .collect();
+    assert_eq!(failed_paths, ["broken", "star"]);
+    assert_eq!(shader_set.records().len(), 4);
+    assert_eq!(device.live_modules().len(), 4);
+
+    shader_set.destroy(&device);
+    assert!(device.live_modules().is_empty());
+}
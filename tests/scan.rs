@@ -0,0 +1,304 @@
+mod common;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{IgnoreReason, MockDevice, ShaderPlan, ShaderStage};
+use common::{spirv, TempDir, FRAGMENT, GL_COMPUTE, VERTEX};
+
+/// Planned shaders as the program name and the stage.
+fn planned(plan: &ShaderPlan) -> Vec<(&str, ShaderStageFlags)> {
+    plan.shaders
+        .iter()
+        .map(|shader| (shader.program_name.as_str(), shader.stage))
+        .collect()
+}
+
+/// Ignored files as the path relative to the directory and the reason.
+fn ignored(plan: &ShaderPlan, dir: &TempDir) -> Vec<(String, IgnoreReason)> {
+    plan.ignored_files
+        .iter()
+        .map(|ignored_file| {
+            let relative_path = ignored_file.path.strip_prefix(dir.path()).unwrap();
+            (
+                relative_path.to_string_lossy().replace('\\', "/"),
+                ignored_file.reason,
+            )
+        })
+        .collect()
+}
+
+fn shaders_dir() -> TempDir {
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    dir.write_words("post/bloom.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir.write_words("post/blur/wide.comp.spv", &spirv(&[(GL_COMPUTE, "main")]));
+    dir.write("post/README.md", b"Post-processing shaders.");
+    dir
+}
+
+#[test]
+fn subdirectories_are_scanned_only_recursively() {
+    let dir = shaders_dir();
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path()).plan().unwrap();
+    assert_eq!(planned(&plan), [("sky", ShaderStageFlags::VERTEX)]);
+    assert_eq!(
+        ignored(&plan, &dir),
+        [(String::from("post"), IgnoreReason::NotRecursive)]
+    );
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .plan()
+        .unwrap();
+    assert_eq!(
+        planned(&plan),
+        [
+            ("post/bloom", ShaderStageFlags::FRAGMENT),
+            ("post/blur/wide", ShaderStageFlags::COMPUTE),
+            ("sky", ShaderStageFlags::VERTEX),
+        ]
+    );
+    assert_eq!(
+        ignored(&plan, &dir),
+        [(String::from("post/README.md"), IgnoreReason::NotShader)]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symbolic_links_to_scanned_directories_are_not_followed() {
+    let dir = shaders_dir();
+    std::os::unix::fs::symlink("..", dir.path().join("post/loop")).unwrap();
+    std::os::unix::fs::symlink("post/blur", dir.path().join("blur")).unwrap();
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .plan()
+        .unwrap();
+    // The link to the sibling directory is scanned like any other subdirectory.
+    assert_eq!(
+        planned(&plan),
+        [
+            ("blur/wide", ShaderStageFlags::COMPUTE),
+            ("post/bloom", ShaderStageFlags::FRAGMENT),
+            ("post/blur/wide", ShaderStageFlags::COMPUTE),
+            ("sky", ShaderStageFlags::VERTEX),
+        ]
+    );
+    assert_eq!(
+        ignored(&plan, &dir),
+        [
+            (String::from("post/README.md"), IgnoreReason::NotShader),
+            (String::from("post/loop"), IgnoreReason::Cycle),
+        ]
+    );
+}
+
+#[test]
+fn hidden_files_and_directories_are_skipped_by_default() {
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    dir.write_words(".draft.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir.write_words(".cache/sea.vert.spv", &spirv(&[(VERTEX, "main")]));
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .plan()
+        .unwrap();
+    assert_eq!(planned(&plan), [("sky", ShaderStageFlags::VERTEX)]);
+    assert_eq!(
+        ignored(&plan, &dir),
+        [
+            (String::from(".cache"), IgnoreReason::Hidden),
+            (String::from(".draft.spv"), IgnoreReason::Hidden),
+        ]
+    );
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .with_skip_hidden(false)
+        .plan()
+        .unwrap();
+    assert_eq!(
+        planned(&plan),
+        [
+            (".cache/sea", ShaderStageFlags::VERTEX),
+            (".draft", ShaderStageFlags::FRAGMENT),
+            ("sky", ShaderStageFlags::VERTEX),
+        ]
+    );
+    assert!(plan.ignored_files.is_empty());
+}
+
+#[test]
+fn include_and_exclude_patterns_filter_files() {
+    let dir = shaders_dir();
+    dir.write_words("sky.frag.debug.spv", &spirv(&[(FRAGMENT, "main")]));
+    dir.write_words("legacy/sea.vert.spv", &spirv(&[(VERTEX, "main")]));
+    dir.write_words("legacy/old/sun.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .with_exclude_pattern("*.debug.spv")
+        .with_exclude_pattern("legacy/**")
+        .plan()
+        .unwrap();
+    assert_eq!(
+        planned(&plan),
+        [
+            ("post/bloom", ShaderStageFlags::FRAGMENT),
+            ("post/blur/wide", ShaderStageFlags::COMPUTE),
+            ("sky", ShaderStageFlags::VERTEX),
+        ]
+    );
+    assert_eq!(
+        ignored(&plan, &dir),
+        [
+            (String::from("legacy/old"), IgnoreReason::Excluded),
+            (String::from("legacy/sea.vert.spv"), IgnoreReason::Excluded),
+            (String::from("post/README.md"), IgnoreReason::NotShader),
+            (String::from("sky.frag.debug.spv"), IgnoreReason::Excluded),
+        ]
+    );
+
+    // The pattern with `/` is matched against the relative path, so `*` doesn't match subdirectories.
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_recursive(true)
+        .with_include_pattern("post/*.spv")
+        .with_exclude_pattern("legacy")
+        .plan()
+        .unwrap();
+    assert_eq!(planned(&plan), [("post/bloom", ShaderStageFlags::FRAGMENT)]);
+    assert_eq!(
+        ignored(&plan, &dir),
+        [
+            (String::from("legacy"), IgnoreReason::Excluded),
+            (String::from("post/README.md"), IgnoreReason::NotIncluded),
+            (
+                String::from("post/blur/wide.comp.spv"),
+                IgnoreReason::NotIncluded
+            ),
+            (
+                String::from("sky.frag.debug.spv"),
+                IgnoreReason::NotIncluded
+            ),
+            (String::from("sky.vert.spv"), IgnoreReason::NotIncluded),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_file_names_are_reported_as_failures() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    use ash_shader_creator::{ErrorPolicy, ShaderCreatorError};
+
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    let path = dir.write_words("sky.frag.spv", &spirv(&[(FRAGMENT, "main")]));
+    let non_utf8_path = dir.path().join(OsStr::from_bytes(b"sky\xff.frag.spv"));
+    std::fs::rename(path, &non_utf8_path).unwrap();
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path()).plan().unwrap();
+    assert_eq!(planned(&plan), [("sky", ShaderStageFlags::VERTEX)]);
+    assert_eq!(plan.invalid_shaders.len(), 1);
+    assert_eq!(plan.invalid_shaders[0].path, non_utf8_path);
+    assert!(matches!(
+        plan.invalid_shaders[0].error,
+        ShaderCreatorError::NonUtf8Path { .. }
+    ));
+
+    let error = ShaderStage::new(&device, dir.path())
+        .build()
+        .err()
+        .expect("the non-UTF-8 name must fail the build");
+    assert!(matches!(error, ShaderCreatorError::NonUtf8Path { .. }));
+
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_error_policy(ErrorPolicy::Collect)
+        .build()
+        .unwrap();
+    assert_eq!(shader_set.records().len(), 1);
+    assert_eq!(shader_set.failures().len(), 1);
+    assert_eq!(shader_set.failures()[0].path, non_utf8_path);
+}
+
+#[cfg(unix)]
+#[test]
+fn skipped_non_utf8_names_dont_fail_the_build() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = TempDir::new();
+    dir.write_words("sky.vert.spv", &spirv(&[(VERTEX, "main")]));
+    let non_utf8_file = |name: &[u8]| {
+        let path = dir.write("non_utf8", b"Not a shader.");
+        std::fs::rename(path, dir.path().join(OsStr::from_bytes(name))).unwrap();
+    };
+    non_utf8_file(b".notes\xff.txt");
+    non_utf8_file(b"notes\xff.txt");
+    non_utf8_file(b"sky\xff.frag.debug.spv");
+    std::fs::create_dir(dir.path().join(OsStr::from_bytes(b"legacy\xff"))).unwrap();
+    let device = MockDevice::new();
+
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_exclude_pattern("*.debug.spv")
+        .plan()
+        .unwrap();
+    assert_eq!(planned(&plan), [("sky", ShaderStageFlags::VERTEX)]);
+    assert!(plan.invalid_shaders.is_empty());
+    assert_eq!(
+        ignored(&plan, &dir),
+        [
+            (String::from(".notes\u{fffd}.txt"), IgnoreReason::Hidden),
+            (String::from("legacy\u{fffd}"), IgnoreReason::NotRecursive),
+            (String::from("notes\u{fffd}.txt"), IgnoreReason::NotShader),
+            (
+                String::from("sky\u{fffd}.frag.debug.spv"),
+                IgnoreReason::Excluded
+            ),
+        ]
+    );
+
+    let shader_set = ShaderStage::new(&device, dir.path())
+        .with_exclude_pattern("*.debug.spv")
+        .build()
+        .unwrap();
+    assert_eq!(shader_set.records().len(), 1);
+    shader_set.destroy(&device);
+
+    // The hidden file is excluded too, so it's skipped whether or not hidden files are.
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_skip_hidden(false)
+        .with_exclude_pattern("*.txt")
+        .with_exclude_pattern("*.debug.spv")
+        .with_exclude_pattern("legacy*")
+        .with_recursive(true)
+        .plan()
+        .unwrap();
+    assert!(plan.invalid_shaders.is_empty());
+    assert_eq!(
+        ignored(&plan, &dir)
+            .iter()
+            .filter(|(_, reason)| *reason == IgnoreReason::Excluded)
+            .count(),
+        4
+    );
+
+    // The subdirectory would be scanned, so its name fails.
+    let plan = ShaderStage::new(&device, dir.path())
+        .with_exclude_pattern("*.debug.spv")
+        .with_recursive(true)
+        .plan()
+        .unwrap();
+    assert_eq!(plan.invalid_shaders.len(), 1);
+    assert_eq!(
+        plan.invalid_shaders[0].path,
+        dir.path().join(OsStr::from_bytes(b"legacy\xff"))
+    );
+}
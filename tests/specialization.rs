@@ -0,0 +1,223 @@
+mod common;
+
+use std::path::PathBuf;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{
+    ErrorPolicy, MockDevice, ShaderCreatorError, ShaderSource, ShaderStage,
+    SpecializationConstantInfo, SpecializationConstantType, SpecializationConstants,
+    SpecializationValue, StageConfig, StageSelector,
+};
+use common::{spirv, Constant, Module, FRAGMENT, VERTEX};
+
+fn sources() -> Vec<ShaderSource> {
+    let sky = Module::new()
+        .entry_point(FRAGMENT, "main")
+        .spec_constant(0, "samples", Constant::U32(4))
+        .spec_constant(1, "exposure", Constant::F32(1.5))
+        .spec_constant(2, "hdr", Constant::Bool(false))
+        .spec_constant(7, "gamma", Constant::F64(2.2))
+        .words();
+
+    vec![
+        ShaderSource::from_words("sky.frag.spv", None, &sky),
+        ShaderSource::from_words("sea.vert.spv", None, &spirv(&[(VERTEX, "main")])),
+    ]
+}
+
+#[test]
+fn specialization_constants_are_reflected() {
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(&device, sources())
+        .load_records()
+        .unwrap();
+
+    let sky = records
+        .iter()
+        .find(|record| record.program_name == "sky")
+        .unwrap();
+    let constant =
+        |constant_id, name: &str, constant_type, default_value| SpecializationConstantInfo {
+            constant_id,
+            name: Some(String::from(name)),
+            constant_type,
+            default_value: Some(default_value),
+        };
+    assert_eq!(
+        sky.reflection.specialization_constants,
+        [
+            constant(
+                0,
+                "samples",
+                SpecializationConstantType::U32,
+                SpecializationValue::U32(4)
+            ),
+            constant(
+                1,
+                "exposure",
+                SpecializationConstantType::F32,
+                SpecializationValue::F32(1.5)
+            ),
+            constant(
+                2,
+                "hdr",
+                SpecializationConstantType::Bool,
+                SpecializationValue::Bool(false)
+            ),
+            constant(
+                7,
+                "gamma",
+                SpecializationConstantType::F64,
+                SpecializationValue::F64(2.2)
+            ),
+        ]
+    );
+    assert_eq!(
+        sky.reflection
+            .specialization_constant(7)
+            .map(|constant| constant.constant_type.size()),
+        Some(8)
+    );
+
+    let sea = records
+        .iter()
+        .find(|record| record.program_name == "sea")
+        .unwrap();
+    assert!(sea.reflection.specialization_constants.is_empty());
+}
+
+#[test]
+fn unknown_constant_fails_the_build_and_destroys_modules() {
+    let device = MockDevice::new();
+    let error = ShaderStage::from_sources(&device, sources())
+        .with_stage_config(
+            StageSelector::Program(String::from("sky")),
+            StageConfig::new().with_specialization_constants(
+                SpecializationConstants::new().set(0, 8u32).set(3, 1u32),
+            ),
+        )
+        .build()
+        .err()
+        .expect("the constant 3 isn't declared");
+
+    match error {
+        ShaderCreatorError::UnknownSpecializationConstant {
+            path,
+            stage,
+            constant_id,
+        } => {
+            assert_eq!(path.to_str(), Some("sky.frag.spv"));
+            assert_eq!(stage, ShaderStageFlags::FRAGMENT);
+            assert_eq!(constant_id, 3);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert_eq!(device.created_modules().len(), 2);
+    assert!(device.live_modules().is_empty());
+}
+
+#[test]
+fn mismatched_constants_are_collected() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, sources())
+        .with_error_policy(ErrorPolicy::Collect)
+        .with_stage_config(
+            StageSelector::Program(String::from("sky")),
+            StageConfig::new().with_specialization_constants(
+                SpecializationConstants::new()
+                    .set(0, -1i32)
+                    .set(1, 0.5f32)
+                    .set(7, 2.0f32),
+            ),
+        )
+        .build()
+        .unwrap();
+
+    assert!(shader_set.program("sky").is_none());
+    assert!(shader_set.program("sea").is_some());
+
+    let failures = shader_set.failures();
+    assert_eq!(failures.len(), 1);
+    match &failures[0].error {
+        ShaderCreatorError::SpecializationConstantMismatch {
+            constant_id,
+            expected,
+            size,
+            value,
+            ..
+        } => {
+            assert_eq!(*constant_id, 7);
+            assert_eq!(*expected, SpecializationConstantType::F64);
+            assert_eq!(*size, 4);
+            assert_eq!(*value, Some(SpecializationValue::F32(2.0)));
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert_eq!(device.live_modules().len(), 1);
+
+    shader_set.destroy(&device);
+    assert!(device.live_modules().is_empty());
+}
+
+#[test]
+fn global_constants_must_be_declared_by_one_stage_of_the_program() {
+    let sun_sources = || {
+        let vertex = Module::new()
+            .entry_point(VERTEX, "main")
+            .spec_constant(1, "instances", Constant::U32(1))
+            .words();
+        let fragment = Module::new()
+            .entry_point(FRAGMENT, "main")
+            .spec_constant(0, "samples", Constant::U32(4))
+            .words();
+        vec![
+            ShaderSource::from_words("sun.vert.spv", None, &vertex),
+            ShaderSource::from_words("sun.frag.spv", None, &fragment),
+        ]
+    };
+    let unknown_constant_id = |error| match error {
+        ShaderCreatorError::UnknownSpecializationConstant {
+            path, constant_id, ..
+        } => (path, constant_id),
+        error => panic!("unexpected error: {}", error),
+    };
+    let device = MockDevice::new();
+
+    // Only the fragment stage declares the constant 0.
+    let shader_set = ShaderStage::from_sources(&device, sun_sources())
+        .with_specialization_constants(SpecializationConstants::new().set(0, 8u32))
+        .build()
+        .unwrap();
+    assert_eq!(shader_set.stages().len(), 2);
+    assert!(shader_set
+        .stages()
+        .iter()
+        .all(|stage| !stage.p_specialization_info.is_null()));
+    shader_set.destroy(&device);
+
+    let error = ShaderStage::from_sources(&device, sun_sources())
+        .with_specialization_constants(SpecializationConstants::new().set(0, 8u32).set(5, 1u32))
+        .build()
+        .err()
+        .expect("no stage declares the constant 5");
+    assert_eq!(
+        unknown_constant_id(error),
+        (PathBuf::from("sun.vert.spv"), 5)
+    );
+
+    // The stage config is checked against its own stage only.
+    let error = ShaderStage::from_sources(&device, sun_sources())
+        .with_stage_config(
+            StageSelector::Stage(ShaderStageFlags::VERTEX),
+            StageConfig::new()
+                .with_specialization_constants(SpecializationConstants::new().set(0, 8u32)),
+        )
+        .build()
+        .err()
+        .expect("the vertex stage doesn't declare the constant 0");
+    assert_eq!(
+        unknown_constant_id(error),
+        (PathBuf::from("sun.vert.spv"), 0)
+    );
+    assert!(device.live_modules().is_empty());
+}
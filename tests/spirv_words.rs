@@ -0,0 +1,97 @@
+mod common;
+
+use ash::vk::ShaderStageFlags;
+use ash_shader_creator::{MockDevice, ShaderCreatorError, ShaderSource, ShaderStage};
+use common::{spirv, VERTEX};
+
+fn bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_ne_bytes()).collect()
+}
+
+fn swapped(words: &[u32]) -> Vec<u32> {
+    words.iter().map(|word| word.swap_bytes()).collect()
+}
+
+fn load_error(source: ShaderSource) -> ShaderCreatorError {
+    let device = MockDevice::new();
+    let error = ShaderStage::from_sources(&device, vec![source])
+        .load_records()
+        .expect_err("the source must be rejected");
+    assert!(device.created_modules().is_empty());
+    error
+}
+
+#[test]
+fn opposite_endian_modules_are_byte_swapped() {
+    let words = spirv(&[(VERTEX, "main")]);
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_bytes("sky.vert.spv", None, &bytes(&words)),
+            ShaderSource::from_bytes("sea.vert.spv", None, &bytes(&swapped(&words))),
+            ShaderSource::from_words("sun.vert.spv", None, &swapped(&words)),
+        ],
+    )
+    .load_records()
+    .unwrap();
+
+    assert_eq!(records.len(), 3);
+    for record in &records {
+        assert_eq!(record.stage, ShaderStageFlags::VERTEX);
+        assert_eq!(record.entry_point, "main");
+    }
+    for recorded in device.created_modules() {
+        assert_eq!(recorded.code, words);
+    }
+}
+
+#[test]
+fn sizes_that_are_not_whole_words_or_shorter_than_the_header_are_rejected() {
+    let mut unaligned = bytes(&spirv(&[(VERTEX, "main")]));
+    unaligned.truncate(unaligned.len() - 2);
+    let unaligned_len = unaligned.len();
+    match load_error(ShaderSource::from_bytes("sky.vert.spv", None, &unaligned)) {
+        ShaderCreatorError::InvalidSpirvSize { path, size } => {
+            assert_eq!(path.to_str(), Some("sky.vert.spv"));
+            assert_eq!(size, unaligned_len);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+
+    let header = &spirv(&[])[..3];
+    match load_error(ShaderSource::from_bytes(
+        "sky.vert.spv",
+        None,
+        &bytes(header),
+    )) {
+        ShaderCreatorError::InvalidSpirvSize { size, .. } => assert_eq!(size, 12),
+        error => panic!("unexpected error: {}", error),
+    }
+    match load_error(ShaderSource::from_words("sky.vert.spv", None, header)) {
+        ShaderCreatorError::InvalidSpirvSize { size, .. } => assert_eq!(size, 12),
+        error => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn modules_without_the_magic_number_are_rejected() {
+    let mut words = spirv(&[(VERTEX, "main")]);
+    words[0] = 0xDEAD_BEEF;
+
+    match load_error(ShaderSource::from_words("sky.vert.spv", None, &words)) {
+        ShaderCreatorError::InvalidSpirvMagic { path, magic } => {
+            assert_eq!(path.to_str(), Some("sky.vert.spv"));
+            assert_eq!(magic, 0xDEAD_BEEF);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    match load_error(ShaderSource::from_bytes(
+        "sky.vert.spv",
+        None,
+        &bytes(&words),
+    )) {
+        ShaderCreatorError::InvalidSpirvMagic { magic, .. } => assert_eq!(magic, 0xDEAD_BEEF),
+        error => panic!("unexpected error: {}", error),
+    }
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use std::{ffi::CStr, path::PathBuf, ptr, slice};
+
+use ash::vk::{
+    PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo, ShaderStageFlags,
+    SpecializationInfo, SpecializationMapEntry,
+};
+use ash_shader_creator::{
+    MockDevice, ShaderCreatorError, ShaderSource, ShaderStage, SpecializationConstants,
+    StageConfig, StageSelector,
+};
+use common::{spirv, Constant, Module, FRAGMENT, GL_COMPUTE, VERTEX};
+
+fn sources() -> Vec<ShaderSource> {
+    vec![
+        ShaderSource::from_words("sky.vert.spv", None, &spirv(&[(VERTEX, "main")])),
+        ShaderSource::from_words("sky.frag.spv", None, &spirv(&[(FRAGMENT, "main")])),
+        ShaderSource::from_words("sea.frag.spv", None, &spirv(&[(FRAGMENT, "main")])),
+        ShaderSource::from_words("cull.comp.spv", None, &spirv(&[(GL_COMPUTE, "main")])),
+    ]
+}
+
+/// The first specialization constant of the stage, `None` without specialization info.
+fn constant(stage: &PipelineShaderStageCreateInfo) -> Option<u32> {
+    let spec_info = unsafe { stage.p_specialization_info.as_ref() }?;
+    let data = unsafe { slice::from_raw_parts(spec_info.p_data as *const u8, spec_info.data_size) };
+    Some(u32::from_ne_bytes([data[0], data[1], data[2], data[3]]))
+}
+
+#[test]
+fn stage_configs_override_defaults_by_precedence() {
+    let map_entry = SpecializationMapEntry {
+        constant_id: 0,
+        offset: 0,
+        size: 4,
+    };
+    let spec_info = |data: &u32| SpecializationInfo {
+        map_entry_count: 1,
+        p_map_entries: &map_entry,
+        data_size: 4,
+        p_data: data as *const u32 as *const _,
+    };
+    let (default_data, fragment_data, sea_data) = (1u32, 2u32, 3u32);
+    let default_spec_info = spec_info(&default_data);
+    let fragment_spec_info = spec_info(&fragment_data);
+    let sea_spec_info = spec_info(&sea_data);
+
+    let device = MockDevice::new();
+    // The spec infos outlive the build.
+    let shader_set = unsafe {
+        ShaderStage::from_sources(&device, sources())
+            .with_spec_info(&default_spec_info)
+            .with_stage_config(
+                StageSelector::File(PathBuf::from("sea.frag.spv")),
+                StageConfig::new().with_spec_info(&sea_spec_info),
+            )
+            .with_stage_config(
+                StageSelector::Stage(ShaderStageFlags::FRAGMENT),
+                StageConfig::new().with_spec_info(&fragment_spec_info),
+            )
+            .with_stage_config(
+                StageSelector::Stage(ShaderStageFlags::COMPUTE),
+                StageConfig::new()
+                    .with_flags(PipelineShaderStageCreateFlags::ALLOW_VARYING_SUBGROUP_SIZE_EXT)
+                    .with_spec_info(ptr::null()),
+            )
+            .build()
+            .unwrap()
+    };
+
+    let stage = |program_name: &str, stage: ShaderStageFlags| {
+        let program = shader_set.program(program_name).unwrap();
+        let index = program
+            .records()
+            .iter()
+            .position(|record| record.stage == stage)
+            .unwrap();
+        program.stages()[index]
+    };
+
+    let compute = stage("cull", ShaderStageFlags::COMPUTE);
+    assert_eq!(
+        compute.flags,
+        PipelineShaderStageCreateFlags::ALLOW_VARYING_SUBGROUP_SIZE_EXT
+    );
+    assert_eq!(constant(&compute), None);
+
+    let vertex = stage("sky", ShaderStageFlags::VERTEX);
+    assert_eq!(vertex.flags, PipelineShaderStageCreateFlags::empty());
+    assert_eq!(constant(&vertex), Some(1));
+    assert_eq!(constant(&stage("sky", ShaderStageFlags::FRAGMENT)), Some(2));
+    assert_eq!(constant(&stage("sea", ShaderStageFlags::FRAGMENT)), Some(3));
+
+    shader_set.destroy(&device);
+}
+
+/// Programs that declare the constants the tests set.
+fn specialized_sources() -> Vec<ShaderSource> {
+    let sky = Module::new()
+        .entry_point(VERTEX, "main")
+        .spec_constant(0, "samples", Constant::U32(4))
+        .words();
+    let cull = Module::new()
+        .entry_point(GL_COMPUTE, "main")
+        .spec_constant(3, "enabled", Constant::Bool(true))
+        .spec_constant(4, "radius", Constant::F64(1.0))
+        .spec_constant(5, "lod_bias", Constant::I32(0))
+        .words();
+
+    vec![
+        ShaderSource::from_words("sky.vert.spv", None, &sky),
+        ShaderSource::from_words("sky.frag.spv", None, &spirv(&[(FRAGMENT, "main")])),
+        ShaderSource::from_words("cull.comp.spv", None, &cull),
+    ]
+}
+
+#[test]
+fn specialization_constants_are_packed_and_owned() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(&device, specialized_sources())
+        .with_specialization_constants(SpecializationConstants::new().set(0, 7u32))
+        .with_stage_config(
+            StageSelector::Program(String::from("cull")),
+            StageConfig::new().with_specialization_constants(
+                SpecializationConstants::new()
+                    .set(3, true)
+                    .set(4, 0.5f64)
+                    .set(5, -1i32)
+                    .set(3, false),
+            ),
+        )
+        .build()
+        .unwrap();
+
+    let sky = shader_set.program("sky").unwrap();
+    assert_eq!(constant(&sky.stages()[0]), Some(7));
+
+    let cull = shader_set.program("cull").unwrap().stages()[0];
+    let spec_info = unsafe { &*cull.p_specialization_info };
+    let map_entries = unsafe {
+        slice::from_raw_parts(spec_info.p_map_entries, spec_info.map_entry_count as usize)
+    };
+    let layout: Vec<_> = map_entries
+        .iter()
+        .map(|entry| (entry.constant_id, entry.offset, entry.size))
+        .collect();
+    assert_eq!(layout, [(3, 0, 4), (4, 8, 8), (5, 16, 4)]);
+    assert_eq!(spec_info.data_size, 20);
+
+    let data = unsafe { slice::from_raw_parts(spec_info.p_data as *const u8, spec_info.data_size) };
+    assert_eq!(data[0..4], 0u32.to_ne_bytes());
+    assert_eq!(data[8..16], 0.5f64.to_ne_bytes());
+    assert_eq!(data[16..20], (-1i32).to_ne_bytes());
+
+    shader_set.destroy(&device);
+}
+
+#[test]
+fn specialization_constants_of_modules_without_constants_are_unknown() {
+    let unknown_constant = |error| match error {
+        ShaderCreatorError::UnknownSpecializationConstant {
+            path, constant_id, ..
+        } => (path, constant_id),
+        error => panic!("unexpected error: {}", error),
+    };
+    let device = MockDevice::new();
+
+    let error = ShaderStage::from_sources(&device, sources())
+        .with_stage_config(
+            StageSelector::File(PathBuf::from("sea.frag.spv")),
+            StageConfig::new()
+                .with_specialization_constants(SpecializationConstants::new().set(42, 1.0f32)),
+        )
+        .build()
+        .err()
+        .expect("sea.frag.spv declares no constants");
+    assert_eq!(unknown_constant(error), (PathBuf::from("sea.frag.spv"), 42));
+
+    let error = ShaderStage::from_sources(&device, sources())
+        .with_specialization_constants(SpecializationConstants::new().set(42, 1.0f32))
+        .build()
+        .err()
+        .expect("no program declares constants");
+    assert_eq!(unknown_constant(error).1, 42);
+    assert!(device.live_modules().is_empty());
+}
+
+#[test]
+fn entry_point_names_and_spec_info_are_copied_into_the_shader_set() {
+    let main_function_name = String::from("main");
+    let map_entries = vec![SpecializationMapEntry {
+        constant_id: 0,
+        offset: 0,
+        size: 4,
+    }];
+    let data = vec![9u32];
+    let spec_info = Box::new(SpecializationInfo {
+        map_entry_count: 1,
+        p_map_entries: map_entries.as_ptr(),
+        data_size: 4,
+        p_data: data.as_ptr().cast(),
+    });
+    let caller_pointers = (
+        main_function_name.as_ptr() as usize,
+        &*spec_info as *const SpecializationInfo as usize,
+        map_entries.as_ptr() as usize,
+        data.as_ptr() as usize,
+    );
+
+    let device = MockDevice::new();
+    // The spec info outlives the build.
+    let shader_set = unsafe {
+        ShaderStage::from_sources(&device, sources())
+            .with_main_function_name(&main_function_name)
+            .with_spec_info(&*spec_info)
+            .build()
+            .unwrap()
+    };
+    drop((main_function_name, map_entries, data, spec_info));
+
+    assert_eq!(shader_set.stages().len(), 4);
+    for stage in shader_set.stages() {
+        let name = unsafe { CStr::from_ptr(stage.p_name) };
+        assert_eq!(name.to_str(), Ok("main"));
+        assert_ne!(stage.p_name as usize, caller_pointers.0);
+
+        let copied_spec_info = unsafe { &*stage.p_specialization_info };
+        assert_ne!(stage.p_specialization_info as usize, caller_pointers.1);
+        assert_ne!(copied_spec_info.p_map_entries as usize, caller_pointers.2);
+        assert_ne!(copied_spec_info.p_data as usize, caller_pointers.3);
+        let map_entry = unsafe { &*copied_spec_info.p_map_entries };
+        assert_eq!(
+            (map_entry.constant_id, map_entry.offset, map_entry.size),
+            (0, 0, 4)
+        );
+        assert_eq!(constant(stage), Some(9));
+    }
+
+    shader_set.destroy(&device);
+}
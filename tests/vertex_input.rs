@@ -0,0 +1,276 @@
+mod common;
+
+use ash::vk::{Format, ShaderStageFlags, VertexInputAttributeDescription, VertexInputRate};
+use ash_shader_creator::{
+    MockDevice, ShaderCreatorError, ShaderSource, ShaderStage, VertexBindingAssignment,
+    VertexInputConfig, VertexInputInfo,
+};
+use common::{Module, FRAGMENT, GL_COMPUTE, VERTEX};
+
+const BUILT_IN_GLOBAL_INVOCATION_ID: u32 = 28;
+const BUILT_IN_VERTEX_INDEX: u32 = 42;
+
+/// The vertex stage with `vec3 position` at the location 0, `ivec2 tile` at 1, `mat4 transform` at 2..=5,
+/// `dvec4 extent` at 6..=7 and `float weights[2]` at 8..=9, declared out of order, and `gl_VertexIndex`.
+fn vertex() -> Vec<u32> {
+    let mut module = Module::new().entry_point(VERTEX, "main");
+    let float = module.type_float(32);
+    let double = module.type_float(64);
+    let int = module.type_int(32, true);
+    let vec3 = module.type_vector(float, 3);
+    let vec4 = module.type_vector(float, 4);
+    let dvec4 = module.type_vector(double, 4);
+    let ivec2 = module.type_vector(int, 2);
+    let mat4 = module.type_matrix(vec4, 4);
+    let weights = module.type_array(float, Some(2));
+    module.input(2, "transform", mat4);
+    module.input(0, "position", vec3);
+    module.input(8, "weights", weights);
+    module.input(6, "extent", dvec4);
+    module.input(1, "tile", ivec2);
+    module.built_in_input(BUILT_IN_VERTEX_INDEX, int);
+    module.words()
+}
+
+/// The fragment stage with the input at the location 0, which isn't the vertex input.
+fn fragment() -> Vec<u32> {
+    let mut module = Module::new().entry_point(FRAGMENT, "main");
+    let float = module.type_float(32);
+    let vec4 = module.type_vector(float, 4);
+    module.input(0, "color", vec4);
+    module.words()
+}
+
+fn attribute(location: u32, binding: u32, format: Format, offset: u32) -> (u32, u32, Format, u32) {
+    (location, binding, format, offset)
+}
+
+fn attributes(attributes: &[VertexInputAttributeDescription]) -> Vec<(u32, u32, Format, u32)> {
+    attributes
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.location,
+                attribute.binding,
+                attribute.format,
+                attribute.offset,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn vertex_inputs_are_reflected() {
+    let device = MockDevice::new();
+    let records = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_words("sky.vert.spv", None, &vertex()),
+            ShaderSource::from_words("sky.frag.spv", None, &fragment()),
+        ],
+    )
+    .load_records()
+    .unwrap();
+
+    let input = |location, name: &str, format, size, attribute_count, locations_per_attribute| {
+        VertexInputInfo {
+            location,
+            name: Some(String::from(name)),
+            format,
+            size,
+            attribute_count,
+            locations_per_attribute,
+        }
+    };
+    let vertex = records
+        .iter()
+        .find(|record| record.stage == ShaderStageFlags::VERTEX)
+        .unwrap();
+    assert_eq!(
+        vertex.reflection.vertex_inputs,
+        [
+            input(0, "position", Format::R32G32B32_SFLOAT, 12, 1, 1),
+            input(1, "tile", Format::R32G32_SINT, 8, 1, 1),
+            input(2, "transform", Format::R32G32B32A32_SFLOAT, 16, 4, 1),
+            input(6, "extent", Format::R64G64B64A64_SFLOAT, 32, 1, 2),
+            input(8, "weights", Format::R32_SFLOAT, 4, 2, 1),
+        ]
+    );
+    let fragment = records
+        .iter()
+        .find(|record| record.stage == ShaderStageFlags::FRAGMENT)
+        .unwrap();
+    assert!(fragment.reflection.vertex_inputs.is_empty());
+}
+
+#[test]
+fn vertex_inputs_are_assigned_to_bindings() {
+    let device = MockDevice::new();
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![ShaderSource::from_words("sky.vert.spv", None, &vertex())],
+    )
+    .build()
+    .unwrap();
+    let sky = shader_set.program("sky").unwrap();
+
+    let interleaved = sky.vertex_input_descriptions(&VertexInputConfig::new());
+    let bindings: Vec<_> = interleaved
+        .bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.stride, binding.input_rate))
+        .collect();
+    assert_eq!(bindings, [(0, 124, VertexInputRate::VERTEX)]);
+    assert_eq!(
+        attributes(&interleaved.attributes),
+        [
+            attribute(0, 0, Format::R32G32B32_SFLOAT, 0),
+            attribute(1, 0, Format::R32G32_SINT, 12),
+            attribute(2, 0, Format::R32G32B32A32_SFLOAT, 20),
+            attribute(3, 0, Format::R32G32B32A32_SFLOAT, 36),
+            attribute(4, 0, Format::R32G32B32A32_SFLOAT, 52),
+            attribute(5, 0, Format::R32G32B32A32_SFLOAT, 68),
+            attribute(6, 0, Format::R64G64B64A64_SFLOAT, 84),
+            attribute(8, 0, Format::R32_SFLOAT, 116),
+            attribute(9, 0, Format::R32_SFLOAT, 120),
+        ]
+    );
+    let create_info = interleaved.create_info();
+    assert_eq!(create_info.vertex_binding_description_count, 1);
+    assert_eq!(create_info.vertex_attribute_description_count, 9);
+    assert_eq!(
+        create_info.p_vertex_attribute_descriptions,
+        interleaved.attributes.as_ptr()
+    );
+
+    let per_attribute = sky.vertex_input_descriptions(
+        &VertexInputConfig::new().with_assignment(VertexBindingAssignment::PerAttribute),
+    );
+    let bindings: Vec<_> = per_attribute
+        .bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.stride))
+        .collect();
+    assert_eq!(bindings, [(0, 12), (1, 8), (2, 64), (6, 32), (8, 8)]);
+    assert_eq!(
+        attributes(&per_attribute.attributes),
+        [
+            attribute(0, 0, Format::R32G32B32_SFLOAT, 0),
+            attribute(1, 1, Format::R32G32_SINT, 0),
+            attribute(2, 2, Format::R32G32B32A32_SFLOAT, 0),
+            attribute(3, 2, Format::R32G32B32A32_SFLOAT, 16),
+            attribute(4, 2, Format::R32G32B32A32_SFLOAT, 32),
+            attribute(5, 2, Format::R32G32B32A32_SFLOAT, 48),
+            attribute(6, 6, Format::R64G64B64A64_SFLOAT, 0),
+            attribute(8, 8, Format::R32_SFLOAT, 0),
+            attribute(9, 8, Format::R32_SFLOAT, 4),
+        ]
+    );
+}
+
+#[test]
+fn location_bindings_and_input_rates_override_the_assignment() {
+    let device = MockDevice::new();
+    let mut compute = Module::new().entry_point(GL_COMPUTE, "main");
+    let uint = compute.type_int(32, false);
+    let uvec3 = compute.type_vector(uint, 3);
+    compute.built_in_input(BUILT_IN_GLOBAL_INVOCATION_ID, uvec3);
+    let shader_set = ShaderStage::from_sources(
+        &device,
+        vec![
+            ShaderSource::from_words("sky.vert.spv", None, &vertex()),
+            ShaderSource::from_words("cull.comp.spv", None, &compute.words()),
+        ],
+    )
+    .build()
+    .unwrap();
+
+    // The per-instance transform and extent are interleaved in the binding 1, the last config of the location wins.
+    let config = VertexInputConfig::new()
+        .with_location_binding(2, 3)
+        .with_location_binding(2, 1)
+        .with_location_binding(6, 1)
+        .with_input_rate(1, VertexInputRate::VERTEX)
+        .with_input_rate(1, VertexInputRate::INSTANCE);
+    let descriptions = shader_set
+        .program("sky")
+        .unwrap()
+        .vertex_input_descriptions(&config);
+    let bindings: Vec<_> = descriptions
+        .bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.stride, binding.input_rate))
+        .collect();
+    assert_eq!(
+        bindings,
+        [
+            (0, 28, VertexInputRate::VERTEX),
+            (1, 96, VertexInputRate::INSTANCE),
+        ]
+    );
+    assert_eq!(
+        attributes(&descriptions.attributes),
+        [
+            attribute(0, 0, Format::R32G32B32_SFLOAT, 0),
+            attribute(1, 0, Format::R32G32_SINT, 12),
+            attribute(2, 1, Format::R32G32B32A32_SFLOAT, 0),
+            attribute(3, 1, Format::R32G32B32A32_SFLOAT, 16),
+            attribute(4, 1, Format::R32G32B32A32_SFLOAT, 32),
+            attribute(5, 1, Format::R32G32B32A32_SFLOAT, 48),
+            attribute(6, 1, Format::R64G64B64A64_SFLOAT, 64),
+            attribute(8, 0, Format::R32_SFLOAT, 20),
+            attribute(9, 0, Format::R32_SFLOAT, 24),
+        ]
+    );
+
+    let descriptions = shader_set
+        .program("cull")
+        .unwrap()
+        .vertex_input_descriptions(&config);
+    assert!(descriptions.bindings.is_empty());
+    assert!(descriptions.attributes.is_empty());
+}
+
+#[test]
+fn cyclic_and_overflowing_vertex_inputs_are_malformed() {
+    // `OpTypeArray %5 %5 %3`, the array of itself.
+    let mut cyclic = Module::new().entry_point(VERTEX, "main");
+    let length = cyclic.uint_spec_constant(0, 2);
+    let weights = cyclic.id();
+    cyclic.declare_array(weights, weights, length);
+    cyclic.input(0, "weights", weights);
+
+    let mut overflowing = Module::new().entry_point(VERTEX, "main");
+    let float = overflowing.type_float(32);
+    let vec4 = overflowing.type_vector(float, 4);
+    let mat4 = overflowing.type_matrix(vec4, 4);
+    let rows = overflowing.type_array(mat4, Some(1 << 16));
+    let transforms = overflowing.type_array(rows, Some(1 << 16));
+    overflowing.input(0, "transforms", transforms);
+
+    let mut last_location = Module::new().entry_point(VERTEX, "main");
+    let float = last_location.type_float(32);
+    let vec4 = last_location.type_vector(float, 4);
+    let mat4 = last_location.type_matrix(vec4, 4);
+    last_location.input(u32::MAX - 1, "transform", mat4);
+
+    for module in [cyclic, overflowing, last_location] {
+        let device = MockDevice::new();
+        let error = ShaderStage::from_sources(
+            &device,
+            vec![ShaderSource::from_words(
+                "scene.vert.spv",
+                None,
+                &module.words(),
+            )],
+        )
+        .load_records()
+        .expect_err("the module is malformed");
+        match error {
+            ShaderCreatorError::MalformedSpirv { path } => {
+                assert_eq!(path.to_str(), Some("scene.vert.spv"))
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}